@@ -1,11 +1,7 @@
 #[cfg(any(feature = "brew-local", feature = "brew-src"))]
 const BREW_VERSION: &str = "4.0.13";
 
-#[cfg(all(
-    feature = "docker",
-    not(feature = "brew-local"),
-    not(feature = "brew-src")
-))]
+#[cfg(not(any(feature = "brew-local", feature = "brew-src")))]
 mod build {
     pub(crate) fn go() {}
 }
@@ -8,9 +8,19 @@ fn main() {
         f = [1, 2, 3, 4];
         g = {'a', 1, [1; 1]};
         h = 1+1i;
+        i = true;
+        j = [1, 2, 3] > 2;
+        k = int32(5);
+        l = uint8([1, 2; 3, 4]);
+        m.a = 1;
+        m.opts.tol = 1e-3;
+        n(2).a = 'x';
+        o = fft([1, 2, 3, 4]);
+        p = zeros(2, 3, 4);
+        q = sparse([1, 2], [1, 2], [3, 4], 3, 3);
     ";
 
-    let results = mocktave::eval(&script);
+    let results = mocktave::eval(script);
 
     // Access the types using explicit conversion
     let _a: f64 = results.get_scalar("a").unwrap();
@@ -21,6 +31,15 @@ fn main() {
     let _f: Vec<Vec<f64>> = results.get_matrix("f").unwrap();
     let _g: Vec<Vec<mocktave::OctaveType>> = results.get_cell_array("g").unwrap();
     let _h: (f64, f64) = results.get_complex_scalar("h").unwrap();
+    let _i: bool = results.get_bool("i").unwrap();
+    let _j: Vec<Vec<bool>> = results.get_bool_matrix("j").unwrap();
+    let _k: i128 = results.get_integer("k").unwrap();
+    let _l: Vec<Vec<i128>> = results.get_integer_matrix("l").unwrap();
+    let _m: mocktave::StructFields = results.get_struct("m").unwrap();
+    let _n: Vec<Vec<mocktave::StructFields>> = results.get_struct_array("n").unwrap();
+    let _o: Vec<Vec<(f64, f64)>> = results.get_complex_matrix("o").unwrap();
+    let _p: mocktave::NdArray<f64> = results.get_nd_array("p").unwrap();
+    let _q: mocktave::SparseParts<f64> = results.get_sparse_matrix("q").unwrap();
 
     // Access the types using implicit conversion
     let _a2: f32 = results.get_unchecked("a").into();
@@ -31,6 +50,13 @@ fn main() {
     let _f2: Vec<Vec<f32>> = results.get_unchecked("f").into();
     let _g2: Vec<Vec<mocktave::OctaveType>> = results.get_unchecked("g").into();
     let _h2: (f32, f32) = results.get_unchecked("h").into();
+    let _i2: bool = results.get_unchecked("i").into();
+    let _j2: Vec<Vec<bool>> = results.get_unchecked("j").into();
+    let _k2: i32 = results.get_unchecked("k").into();
+    let _l2: Vec<Vec<u8>> = results.get_unchecked("l").into();
+    let _o2: Vec<Vec<(f32, f32)>> = results.get_unchecked("o").into();
+    let _p2: mocktave::NdArray<f32> = results.get_unchecked("p").into();
+    let _q2: Vec<Vec<f32>> = results.get_unchecked("q").into();
 
     // Directly index to access the underlying value
     let _a3: &mocktave::OctaveType = &results["a"];
@@ -41,6 +67,19 @@ fn main() {
     let _f3: &mocktave::OctaveType = &results["f"];
     let _g3: &mocktave::OctaveType = &results["g"];
     let _h3: &mocktave::OctaveType = &results["h"];
+    let _i3: &mocktave::OctaveType = &results["i"];
+    let _j3: &mocktave::OctaveType = &results["j"];
+    let _k3: &mocktave::OctaveType = &results["k"];
+    let _l3: &mocktave::OctaveType = &results["l"];
+    let _m3: &mocktave::OctaveType = &results["m"];
+    let _n3: &mocktave::OctaveType = &results["n"];
+    let _o3: &mocktave::OctaveType = &results["o"];
+    let _p3: &mocktave::OctaveType = &results["p"];
+    let _q3: &mocktave::OctaveType = &results["q"];
+
+    // Reach into structs with a path
+    let _tol: mocktave::OctaveType = results.get_path("m.opts.tol").unwrap();
+    let _n2a: mocktave::OctaveType = results.get_path("n(2).a").unwrap();
 
     println!("{results}");
 }
@@ -0,0 +1,24 @@
+fn main() {
+    // Keep a few interpreters warm so that every tolerance runs in a session of its own
+    let pool = mocktave::InterpreterPool::new(4);
+
+    // Solve the same ODE once per tolerance, in parallel
+    let script = "
+        opt = odeset (\"RelTol\", tol, \"AbsTol\", realmin);
+        [t, y] = ode45 (@(t, y) -y, [0, 1], 1, opt);
+        h = 1 / (numel (t) - 1);
+        err = norm (y .* exp (t) - 1, Inf);
+    ";
+    let tolerances = (0..4).map(|i| 1e-5 / 10_f64.powi(i));
+    let results = pool.map(tolerances.map(|tol| [("tol", tol)]), script);
+
+    // Estimate the order of convergence from consecutive runs
+    let steps = results
+        .iter()
+        .map(|res| (res.get_scalar("h").unwrap(), res.get_scalar("err").unwrap()))
+        .collect::<Vec<_>>();
+    for pair in steps.windows(2) {
+        let ((h0, err0), (h1, err1)) = (pair[0], pair[1]);
+        println!("p = {}", (err1.ln() - err0.ln()) / (h1.ln() - h0.ln()));
+    }
+}
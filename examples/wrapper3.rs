@@ -0,0 +1,10 @@
+use mocktave::wrap_n;
+fn main() {
+    let eig = wrap_n("eig".into());
+
+    let a = vec![vec![2.0, 0.0], vec![0.0, 3.0]];
+
+    let (vectors, values): (Vec<Vec<f64>>, Vec<Vec<f64>>) = eig([a]);
+    assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    assert_eq!(values, vec![vec![2.0, 0.0], vec![0.0, 3.0]]);
+}
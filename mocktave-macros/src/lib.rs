@@ -0,0 +1,125 @@
+#![warn(clippy::all)]
+#![warn(missing_docs)]
+//! Procedural macros for [mocktave](https://docs.rs/mocktave). Use them through the re-exports in
+//! that crate rather than depending on this one directly.
+
+use proc_macro::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Attribute, FnArg, Pat, ReturnType, Signature, Type, Visibility};
+
+/// Generate strongly typed Rust functions that call the Octave functions of the same name.
+#[proc_macro]
+pub fn octave_fn(input: TokenStream) -> TokenStream {
+    let Declarations(declarations) = parse_macro_input!(input as Declarations);
+    declarations
+        .into_iter()
+        .map(|declaration| match declaration.expand() {
+            Ok(tokens) => tokens,
+            Err(error) => error.to_compile_error(),
+        })
+        .collect::<proc_macro2::TokenStream>()
+        .into()
+}
+
+/// Any number of function signatures, each ending in a semicolon
+struct Declarations(Vec<Declaration>);
+
+impl Parse for Declarations {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut declarations = Vec::new();
+        while !input.is_empty() {
+            declarations.push(input.parse()?);
+        }
+        Ok(Declarations(declarations))
+    }
+}
+
+/// A single function signature, like `pub fn eig(a: Vec<Vec<f64>>) -> Vec<Vec<f64>>;`
+struct Declaration {
+    attributes: Vec<Attribute>,
+    visibility: Visibility,
+    signature: Signature,
+}
+
+impl Parse for Declaration {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attributes = input.call(Attribute::parse_outer)?;
+        let visibility = input.parse()?;
+        let signature = input.parse()?;
+        input.parse::<syn::Token![;]>()?;
+        Ok(Declaration {
+            attributes,
+            visibility,
+            signature,
+        })
+    }
+}
+
+impl Declaration {
+    /// Write out the body of the function
+    fn expand(self) -> syn::Result<proc_macro2::TokenStream> {
+        let Declaration {
+            attributes,
+            visibility,
+            signature,
+        } = self;
+
+        if let Some(token) = signature.asyncness {
+            return Err(syn::Error::new_spanned(
+                token,
+                "Octave functions can't be async",
+            ));
+        }
+        if !signature.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &signature.generics,
+                "Octave functions can't be generic",
+            ));
+        }
+
+        let mut arguments = Vec::new();
+        for input in signature.inputs.iter() {
+            match input {
+                FnArg::Typed(argument) => match argument.pat.as_ref() {
+                    Pat::Ident(ident) => arguments.push(ident.ident.clone()),
+                    pattern => {
+                        return Err(syn::Error::new_spanned(
+                            pattern,
+                            "Arguments must be plain identifiers",
+                        ))
+                    }
+                },
+                FnArg::Receiver(receiver) => {
+                    return Err(syn::Error::new_spanned(
+                        receiver,
+                        "Octave functions can't take `self`",
+                    ))
+                }
+            }
+        }
+
+        // A tuple asks for one output per element, anything else is a single output
+        let (outputs, result) = match &signature.output {
+            ReturnType::Default => (quote!(()), quote!(outputs)),
+            ReturnType::Type(_, output) => match output.as_ref() {
+                Type::Tuple(_) => (output.to_token_stream(), quote!(outputs)),
+                output => (quote!((#output,)), quote!(outputs.0)),
+            },
+        };
+
+        let name = signature.ident.to_string();
+        let name = name.trim_start_matches("r#");
+
+        Ok(quote! {
+            #(#attributes)*
+            #visibility #signature {
+                let outputs: #outputs = ::mocktave::__private::call(
+                    #name,
+                    ::std::vec![#(::std::convert::Into::<::mocktave::OctaveType>::into(#arguments)),*],
+                );
+                #result
+            }
+        })
+    }
+}
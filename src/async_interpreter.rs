@@ -0,0 +1,263 @@
+//! An interpreter for async code, which runs on the caller's tokio runtime instead of blocking.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::backend::Backend;
+use crate::builder::InterpreterBuilder;
+use crate::call::Call;
+use crate::options::Options;
+use crate::{InterpreterResults, MocktaveError, OctaveType, Outputs};
+
+/// The same as [`Interpreter`](crate::Interpreter), but with `async` methods that run on the
+/// caller's tokio runtime. Use this one from async code, where the blocking interpreter would try
+/// to start a runtime inside a runtime.
+/// ```
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let mut interp = mocktave::AsyncInterpreter::try_new().await.unwrap();
+/// interp.eval("a = 1").await;
+/// let res = interp.eval("b = a + 1").await;
+/// assert_eq!(res.get_scalar("b").unwrap(), 2_f64);
+/// interp.close().await.unwrap();
+/// # });
+/// ```
+pub struct AsyncInterpreter {
+    backend: Box<dyn Backend>,
+    pub(crate) options: Options,
+}
+
+impl AsyncInterpreter {
+    /// Start a new interpreter, reporting failures instead of panicking.
+    pub async fn try_new() -> Result<Self, MocktaveError> {
+        InterpreterBuilder::default().try_build_async().await
+    }
+
+    /// Configure where and how the interpreter runs Octave before starting it.
+    /// ```
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let mut interp = mocktave::AsyncInterpreter::builder()
+    ///     .tag("8.1.0")
+    ///     .build_async()
+    ///     .await;
+    /// let res = interp.eval("v = OCTAVE_VERSION").await;
+    /// assert_eq!(res.get_string("v").unwrap(), "8.1.0");
+    /// # });
+    /// ```
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
+
+    /// Run scripts with `backend`, like [`Interpreter::with_backend`].
+    ///
+    /// [`Interpreter::with_backend`]: crate::Interpreter::with_backend
+    pub fn with_backend(backend: impl Backend + 'static) -> Self {
+        AsyncInterpreter::new(Box::new(backend), Options::default())
+    }
+
+    /// Run scripts with `backend`.
+    pub(crate) fn new(backend: Box<dyn Backend>, options: Options) -> Self {
+        AsyncInterpreter { backend, options }
+    }
+
+    /// Choose whether whatever scripts print is also echoed, like [`Interpreter::set_echo`].
+    ///
+    /// [`Interpreter::set_echo`]: crate::Interpreter::set_echo
+    pub fn set_echo(&mut self, echo: bool) {
+        self.options.echo = echo;
+    }
+
+    /// Raise warnings with the identifier `identifier` as errors, like
+    /// [`Interpreter::promote_warning`].
+    ///
+    /// [`Interpreter::promote_warning`]: crate::Interpreter::promote_warning
+    pub fn promote_warning(&mut self, identifier: &str) {
+        self.options.promote(identifier);
+    }
+
+    /// Give up on scripts that take longer than `timeout`, like [`Interpreter::set_timeout`].
+    ///
+    /// [`Interpreter::set_timeout`]: crate::Interpreter::set_timeout
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.options.timeout = timeout;
+    }
+
+    /// Run a script in the interpreter's session, like [`Interpreter::eval`]. Dropping the future
+    /// before it is done doesn't stop the script: it runs to the end and the next script waits for
+    /// it, but its output never shows up anywhere.
+    ///
+    /// [`Interpreter::eval`]: crate::Interpreter::eval
+    pub async fn eval(&mut self, input: &str) -> InterpreterResults {
+        self.run_with_timeout(input, self.options.timeout)
+            .await
+            .expect("Could not evaluate Octave code.")
+    }
+
+    /// Run a script like [`AsyncInterpreter::eval`], but report failures instead of panicking.
+    /// ```
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let mut interp = mocktave::AsyncInterpreter::try_new().await.unwrap();
+    /// let err = interp.try_eval("error('boom')").await.unwrap_err();
+    /// assert!(matches!(err, mocktave::MocktaveError::Octave { .. }));
+    /// # });
+    /// ```
+    pub async fn try_eval(&mut self, input: &str) -> Result<InterpreterResults, MocktaveError> {
+        self.try_eval_with_timeout(input, self.options.timeout)
+            .await
+    }
+
+    /// Run a script like [`AsyncInterpreter::eval`], using `timeout` instead of the limit set
+    /// with [`AsyncInterpreter::set_timeout`].
+    pub async fn eval_with_timeout(
+        &mut self,
+        input: &str,
+        timeout: Option<Duration>,
+    ) -> InterpreterResults {
+        self.run_with_timeout(input, timeout)
+            .await
+            .expect("Could not evaluate Octave code.")
+    }
+
+    /// Run a script like [`AsyncInterpreter::try_eval`], using `timeout` instead of the limit
+    /// set with [`AsyncInterpreter::set_timeout`].
+    pub async fn try_eval_with_timeout(
+        &mut self,
+        input: &str,
+        timeout: Option<Duration>,
+    ) -> Result<InterpreterResults, MocktaveError> {
+        let results = self.run_with_timeout(input, timeout).await?;
+        match results.error() {
+            Some(error) => Err(error.clone().into()),
+            None => Ok(results),
+        }
+    }
+
+    /// Create the variable `name` in the session, like [`Interpreter::set`].
+    ///
+    /// [`Interpreter::set`]: crate::Interpreter::set
+    pub async fn set(&mut self, name: &str, value: impl Into<OctaveType>) {
+        self.try_set(name, value)
+            .await
+            .expect("Could not set the variable.")
+    }
+
+    /// Create a variable like [`AsyncInterpreter::set`], but report failures instead of
+    /// panicking.
+    pub async fn try_set(
+        &mut self,
+        name: &str,
+        value: impl Into<OctaveType>,
+    ) -> Result<(), MocktaveError> {
+        self.load(&[(name.to_string(), value.into())]).await
+    }
+
+    /// Call the Octave function `function` with `args` and return its first output, like
+    /// [`Interpreter::call`].
+    /// ```
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let mut interp = mocktave::AsyncInterpreter::try_new().await.unwrap();
+    /// let norm: f64 = interp.call("norm", &[vec![vec![3.0, 4.0]].into()]).await;
+    /// assert_eq!(norm, 5.0);
+    /// # });
+    /// ```
+    ///
+    /// [`Interpreter::call`]: crate::Interpreter::call
+    pub async fn call<Z: From<OctaveType>>(&mut self, function: &str, args: &[OctaveType]) -> Z {
+        self.try_call(function, args)
+            .await
+            .expect("Could not call the function.")
+    }
+
+    /// Call a function like [`AsyncInterpreter::call`], but report failures instead of
+    /// panicking.
+    pub async fn try_call<Z: From<OctaveType>>(
+        &mut self,
+        function: &str,
+        args: &[OctaveType],
+    ) -> Result<Z, MocktaveError> {
+        let (output,) = self.try_call_n(function, args).await?;
+        Ok(output)
+    }
+
+    /// Call a function that returns more than one value, like [`Interpreter::call_n`].
+    ///
+    /// [`Interpreter::call_n`]: crate::Interpreter::call_n
+    pub async fn call_n<Z: Outputs>(&mut self, function: &str, args: &[OctaveType]) -> Z {
+        self.try_call_n(function, args)
+            .await
+            .expect("Could not call the function.")
+    }
+
+    /// Call a function like [`AsyncInterpreter::call_n`], but report failures instead of
+    /// panicking.
+    pub async fn try_call_n<Z: Outputs>(
+        &mut self,
+        function: &str,
+        args: &[OctaveType],
+    ) -> Result<Z, MocktaveError> {
+        let call = Call::new(function, args, Z::COUNT);
+        self.load(&call.inputs).await?;
+        // Outputs that were assigned before a failure would stay behind, so clean up either way
+        let outputs = match self.try_eval(&call.script()).await {
+            Ok(results) => call.outputs(&results),
+            Err(err) => Err(err),
+        };
+        if let Some(cleanup) = call.cleanup() {
+            let cleared = self.run_with_timeout(&cleanup, self.options.timeout).await;
+            let outputs = outputs?;
+            cleared?;
+            return Ok(outputs);
+        }
+        outputs
+    }
+
+    /// Stop the interpreter and release its backend, which removes the container or ends the
+    /// Octave process. Dropping the interpreter does the same in the background, but only this
+    /// waits for it to finish, which matters right before the runtime shuts down.
+    pub async fn close(mut self) -> Result<(), MocktaveError> {
+        self.backend.close().await
+    }
+
+    /// Create `variables` in the session, keeping the values exactly as they are.
+    pub(crate) async fn load(
+        &mut self,
+        variables: &[(String, OctaveType)],
+    ) -> Result<(), MocktaveError> {
+        self.backend.load(variables).await
+    }
+
+    /// Run a script, keeping any error Octave raised along the way in the results, and giving
+    /// up after `timeout`.
+    pub(crate) async fn run_with_timeout(
+        &mut self,
+        input: &str,
+        timeout: Option<Duration>,
+    ) -> Result<InterpreterResults, MocktaveError> {
+        let eval = self.backend.eval(input, &self.options.promoted);
+        let results = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, eval)
+                .await
+                .unwrap_or(Err(MocktaveError::Timeout(timeout))),
+            None => eval.await,
+        };
+        // Whatever ran out of time may still be running, so start over
+        if let Err(MocktaveError::Timeout(_)) = results {
+            self.backend.restart().await?;
+        }
+        let results = results?;
+        if self.options.echo {
+            print!("{}", results.stdout);
+            eprint!("{}", results.stderr);
+        }
+        Ok(results)
+    }
+}
+
+/// The runtime the blocking API runs on. It is started the first time it is needed and shared
+/// by every interpreter from then on.
+pub(crate) fn runtime() -> Result<&'static tokio::runtime::Runtime, MocktaveError> {
+    static RUNTIME: OnceLock<Result<tokio::runtime::Runtime, String>> = OnceLock::new();
+    RUNTIME
+        .get_or_init(|| tokio::runtime::Runtime::new().map_err(|error| error.to_string()))
+        .as_ref()
+        .map_err(|error| MocktaveError::BackendUnavailable(error.clone()))
+}
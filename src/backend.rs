@@ -0,0 +1,58 @@
+//! The places Octave can run, behind a common interface.
+
+use futures_util::future::BoxFuture;
+
+use crate::session::escape;
+use crate::{writer, InterpreterResults, MocktaveError, OctaveType};
+
+/// Somewhere to run Octave code, like a Docker container or a local Octave process. Interpreters
+/// only talk to Octave through this trait, so anything that implements it can stand in for Octave.
+///
+/// The methods return boxed futures so that interpreters can hold any backend as a
+/// `Box<dyn Backend>`. [`BoxFuture`] is the same as `Pin<Box<dyn Future<Output = T> + Send + 'a>>`.
+pub trait Backend: Send {
+    /// Run `script` and report the workspace afterwards. Warnings with an identifier in
+    /// `promoted` are raised as errors, and all others as warnings, whatever was promoted for
+    /// earlier scripts. An error raised by the script itself is part of the results rather than
+    /// an `Err`, which is reserved for problems with the backend.
+    fn eval<'a>(
+        &'a mut self,
+        script: &'a str,
+        promoted: &'a [String],
+    ) -> BoxFuture<'a, Result<InterpreterResults, MocktaveError>>;
+
+    /// Create `variables` in the workspace, keeping the values exactly as they are.
+    fn load<'a>(
+        &'a mut self,
+        variables: &'a [(String, OctaveType)],
+    ) -> BoxFuture<'a, Result<(), MocktaveError>>;
+
+    /// Stop whatever is running and start over with an empty workspace. This is how scripts that
+    /// run out of time are stopped.
+    fn restart(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>>;
+
+    /// Stop Octave and release everything the backend holds on to, waiting until that is done.
+    fn close(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>>;
+}
+
+/// The contents of a workspace file that holds `variables`.
+pub(crate) fn payload(variables: &[(String, OctaveType)]) -> Result<String, MocktaveError> {
+    variables
+        .iter()
+        .map(|(name, value)| writer::block(name, value))
+        .collect()
+}
+
+/// A script that loads the workspace file at `path` and deletes it afterwards.
+pub(crate) fn load_script(path: &str) -> String {
+    let path = escape(path);
+    format!("load(\"-text\", \"{path}\");\ndelete(\"{path}\");")
+}
+
+/// Turn an error raised by one of our own scripts into an `Err`.
+pub(crate) fn check(results: InterpreterResults) -> Result<(), MocktaveError> {
+    match results.error() {
+        Some(error) => Err(error.clone().into()),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,348 @@
+//! Configuration for where and how an interpreter runs Octave.
+
+use std::fmt::{Debug, Formatter};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::async_interpreter::{self, AsyncInterpreter};
+use crate::backend::Backend;
+use crate::cassette::{Cassette, RecordMode};
+#[cfg(feature = "docker")]
+use crate::docker::{Connection, DockerBackend, DockerSettings, PullPolicy};
+use crate::local_process::LocalProcess;
+use crate::options::Options;
+use crate::pool::InterpreterPool;
+use crate::{Interpreter, MocktaveError};
+
+/// Makes a backend for every interpreter that is built.
+type MakeBackend = Arc<dyn Fn() -> Box<dyn Backend> + Send + Sync>;
+
+/// Where the interpreter runs Octave.
+#[derive(Clone)]
+enum Target {
+    /// In a Docker container, see [`DockerBackend`].
+    #[cfg(feature = "docker")]
+    Docker,
+    /// In a process on this machine, see [`LocalProcess`].
+    LocalProcess,
+    /// In whatever backend the closure makes.
+    Custom(MakeBackend),
+}
+
+impl Debug for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "docker")]
+            Target::Docker => write!(f, "Docker"),
+            Target::LocalProcess => write!(f, "LocalProcess"),
+            Target::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        #[cfg(feature = "docker")]
+        return Target::Docker;
+        #[cfg(not(feature = "docker"))]
+        return Target::LocalProcess;
+    }
+}
+
+/// Configure where an interpreter runs Octave and how, for example to pin the Octave version that
+/// legacy code was validated against. Start one with [`Interpreter::builder`] or
+/// [`AsyncInterpreter::builder`]. Octave runs in a Docker container unless
+/// [`InterpreterBuilder::local_process`] is used, or the `docker` feature is disabled.
+/// ```
+/// use mocktave::{Interpreter, PullPolicy};
+/// let mut interp = Interpreter::builder()
+///     .image("gnuoctave/octave")
+///     .tag("8.1.0")
+///     .pull(PullPolicy::IfMissing)
+///     .label("owner", "ci")
+///     .env("OMP_NUM_THREADS", "1")
+///     .build();
+/// let res = interp.eval("n = getenv('OMP_NUM_THREADS')");
+/// assert_eq!(res.get_string("n").unwrap(), "1");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InterpreterBuilder {
+    target: Target,
+    #[cfg(feature = "docker")]
+    docker: DockerSettings,
+    program: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    working_dir: Option<String>,
+    cassette: Option<PathBuf>,
+    record: Option<RecordMode>,
+    options: Options,
+}
+
+impl InterpreterBuilder {
+    /// Use the image `image`, which can include a registry like
+    /// `registry.example.com/gnuoctave/octave`. Defaults to `gnuoctave/octave`.
+    #[cfg(feature = "docker")]
+    pub fn image(mut self, image: &str) -> Self {
+        self.docker.image = image.to_string();
+        self
+    }
+
+    /// Use the tag `tag` of the image. Defaults to `8.1.0`.
+    #[cfg(feature = "docker")]
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.docker.tag = tag.to_string();
+        self
+    }
+
+    /// Decide when the image is pulled. Defaults to [`PullPolicy::Always`].
+    #[cfg(feature = "docker")]
+    pub fn pull(mut self, pull: PullPolicy) -> Self {
+        self.docker.pull = pull;
+        self
+    }
+
+    /// Give the container the name `name`. Docker picks a random name otherwise. Since names
+    /// have to be unique, the containers of a pool made with [`InterpreterBuilder::build_pool`]
+    /// are named `name-1`, `name-2` and so on, counting every container the pool starts.
+    /// ```
+    /// let pool = mocktave::Interpreter::builder()
+    ///     .name("mocktave-name-doctest")
+    ///     .build_pool(2);
+    /// let (mut first, mut second) = (pool.lease(), pool.lease());
+    /// assert_eq!(first.eval("x = 1").get_scalar("x"), Some(1.0));
+    /// assert_eq!(second.eval("x = 2").get_scalar("x"), Some(2.0));
+    /// ```
+    #[cfg(feature = "docker")]
+    pub fn name(mut self, name: &str) -> Self {
+        self.docker.name = Some(name.to_string());
+        self
+    }
+
+    /// Add the label `key=value` to the container.
+    #[cfg(feature = "docker")]
+    pub fn label(mut self, key: &str, value: &str) -> Self {
+        self.docker
+            .labels
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Set the environment variable `key` to `value` for Octave.
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Start Octave in the directory `dir`, which is a directory inside the container when Octave
+    /// runs in Docker.
+    pub fn working_dir(mut self, dir: &str) -> Self {
+        self.working_dir = Some(dir.to_string());
+        self
+    }
+
+    /// Run Octave in a Docker container, which is the default when the `docker` feature is enabled.
+    #[cfg(feature = "docker")]
+    pub fn docker(mut self) -> Self {
+        self.target = Target::Docker;
+        self
+    }
+
+    /// Run Octave as a process on this machine instead of in a container, using the first of
+    /// `octave-cli` and `octave` found on `PATH`. No Docker daemon is needed, but the results
+    /// depend on whichever version of Octave is installed.
+    /// ```
+    /// let mut interp = mocktave::Interpreter::builder()
+    ///     .local_process()
+    ///     .env("OMP_NUM_THREADS", "1")
+    ///     .build();
+    /// let res = interp.eval("n = getenv('OMP_NUM_THREADS')");
+    /// assert_eq!(res.get_string("n").unwrap(), "1");
+    /// ```
+    pub fn local_process(mut self) -> Self {
+        self.target = Target::LocalProcess;
+        self
+    }
+
+    /// Run Octave as a process on this machine like [`InterpreterBuilder::local_process`], but
+    /// start it from `program` instead of searching `PATH`.
+    /// ```
+    /// use mocktave::{Interpreter, MocktaveError};
+    /// let error = Interpreter::builder()
+    ///     .octave_path("/does/not/exist/octave-cli")
+    ///     .try_build()
+    ///     .err()
+    ///     .unwrap();
+    /// assert!(matches!(error, MocktaveError::BackendUnavailable(_)));
+    /// ```
+    pub fn octave_path(mut self, program: impl Into<PathBuf>) -> Self {
+        self.target = Target::LocalProcess;
+        self.program = Some(program.into());
+        self
+    }
+
+    /// Run scripts with a backend made by `make` instead of running Octave, for example a
+    /// [`FakeBackend`](crate::FakeBackend). Every interpreter that is built gets a backend of its
+    /// own.
+    /// ```
+    /// use mocktave::{FakeBackend, Interpreter, InterpreterResults};
+    /// let builder = Interpreter::builder().backend(|| {
+    ///     FakeBackend::new().on_script("a = 1", InterpreterResults::default().with_variable("a", 1.0))
+    /// });
+    /// let mut interp = builder.build();
+    /// assert_eq!(interp.eval("a = 1").get_scalar("a"), Some(1.0));
+    /// ```
+    pub fn backend<B: Backend + 'static>(
+        mut self,
+        make: impl Fn() -> B + Send + Sync + 'static,
+    ) -> Self {
+        self.target = Target::Custom(Arc::new(move || Box::new(make())));
+        self
+    }
+
+    /// Answer scripts from recordings kept in `dir`, and only start Octave to record scripts that
+    /// have no recording yet, as described for [`Cassette`](crate::Cassette). Whether scripts are
+    /// recorded is read from the `MOCKTAVE_RECORD` environment variable unless
+    /// [`InterpreterBuilder::record_mode`] is used.
+    /// ```
+    /// use mocktave::{Interpreter, MocktaveError, RecordMode};
+    /// let dir = std::env::temp_dir().join("mocktave-cassette-doctest");
+    /// let mut interp = Interpreter::builder()
+    ///     .cassette(&dir)
+    ///     .record_mode(RecordMode::ReplayOnly)
+    ///     .build();
+    /// let err = interp.try_eval("x = 1 + 1").unwrap_err();
+    /// assert_eq!(err, MocktaveError::Unmatched("x = 1 + 1".to_string()));
+    /// ```
+    pub fn cassette(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cassette = Some(dir.into());
+        self
+    }
+
+    /// Decide whether a cassette records scripts, instead of reading it from the
+    /// `MOCKTAVE_RECORD` environment variable.
+    pub fn record_mode(mut self, mode: RecordMode) -> Self {
+        self.record = Some(mode);
+        self
+    }
+
+    /// Connect to the Docker daemon through the Unix socket at `path`.
+    #[cfg(feature = "docker")]
+    pub fn socket(mut self, path: &str) -> Self {
+        self.docker.connection = Connection::Socket(path.to_string());
+        self
+    }
+
+    /// Connect to the Docker daemon at `host`, given in the same form as `DOCKER_HOST`, like
+    /// `unix:///var/run/docker.sock` or `tcp://build-box:2375`. By default `DOCKER_HOST` itself
+    /// is used if it is set.
+    #[cfg(feature = "docker")]
+    pub fn docker_host(mut self, host: &str) -> Self {
+        self.docker.connection = Connection::Host(host.to_string());
+        self
+    }
+
+    /// Echo whatever scripts print, like [`Interpreter::set_echo`].
+    pub fn echo(mut self, echo: bool) -> Self {
+        self.options.echo = echo;
+        self
+    }
+
+    /// Raise warnings with the identifier `identifier` as errors, like
+    /// [`Interpreter::promote_warning`].
+    pub fn promote_warning(mut self, identifier: &str) -> Self {
+        self.options.promote(identifier);
+        self
+    }
+
+    /// Give up on scripts that take longer than `timeout`, like [`Interpreter::set_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    /// Start the interpreter. Panics if it can't be started.
+    pub fn build(self) -> Interpreter {
+        self.try_build().expect("Could not start the interpreter.")
+    }
+
+    /// Start the interpreter, reporting failures instead of panicking.
+    /// ```
+    /// use mocktave::{Interpreter, MocktaveError, PullPolicy};
+    /// let error = Interpreter::builder()
+    ///     .image("mocktave/does-not-exist")
+    ///     .pull(PullPolicy::Never)
+    ///     .try_build()
+    ///     .err()
+    ///     .unwrap();
+    /// assert!(matches!(error, MocktaveError::ImagePull(_)));
+    /// ```
+    pub fn try_build(self) -> Result<Interpreter, MocktaveError> {
+        let inner = async_interpreter::runtime()?.block_on(self.try_build_async())?;
+        Ok(Interpreter { inner })
+    }
+
+    /// Start an [`AsyncInterpreter`]. Panics if it can't be started.
+    pub async fn build_async(self) -> AsyncInterpreter {
+        self.try_build_async()
+            .await
+            .expect("Could not start the interpreter.")
+    }
+
+    /// Start an [`AsyncInterpreter`], reporting failures instead of panicking.
+    pub async fn try_build_async(self) -> Result<AsyncInterpreter, MocktaveError> {
+        let options = self.options.clone();
+        Ok(AsyncInterpreter::new(
+            self.try_build_backend().await?,
+            options,
+        ))
+    }
+
+    /// Make an [`InterpreterPool`] of up to `size` interpreters with these settings. Nothing is
+    /// started until the pool lends out its first interpreter.
+    /// ```
+    /// let pool = mocktave::Interpreter::builder()
+    ///     .env("OMP_NUM_THREADS", "1")
+    ///     .build_pool(4);
+    /// let res = pool.lease().eval("n = getenv('OMP_NUM_THREADS')");
+    /// assert_eq!(res.get_string("n").unwrap(), "1");
+    /// ```
+    pub fn build_pool(self, size: usize) -> InterpreterPool {
+        let options = self.options.clone();
+        InterpreterPool::with_builder(self, options, size)
+    }
+
+    /// Tell the `n`th interpreter a pool starts apart from the others, by giving its container a
+    /// name of its own.
+    #[cfg_attr(not(feature = "docker"), allow(unused_mut, unused_variables))]
+    pub(crate) fn numbered(mut self, n: usize) -> Self {
+        #[cfg(feature = "docker")]
+        if let Some(name) = &mut self.docker.name {
+            *name = format!("{name}-{n}");
+        }
+        self
+    }
+
+    /// Start the backend the builder describes.
+    pub(crate) async fn try_build_backend(mut self) -> Result<Box<dyn Backend>, MocktaveError> {
+        if let Some(dir) = self.cassette.take() {
+            let mode = match self.record {
+                Some(mode) => mode,
+                None => RecordMode::from_env()?,
+            };
+            return Ok(Box::new(Cassette::new(dir, mode, self)));
+        }
+
+        let working_dir = self.working_dir.as_deref();
+        Ok(match &self.target {
+            #[cfg(feature = "docker")]
+            Target::Docker => {
+                Box::new(DockerBackend::start(&self.docker, &self.env, working_dir).await?)
+            }
+            Target::LocalProcess => Box::new(
+                LocalProcess::start(self.program.as_deref(), &self.env, working_dir).await?,
+            ),
+            Target::Custom(make) => make(),
+        })
+    }
+}
@@ -0,0 +1,112 @@
+//! Calling Octave functions on values sent as workspace data, which works the same way for the
+//! blocking and the async interpreter.
+
+use crate::{InterpreterResults, MocktaveError, OctaveType, Outputs};
+
+/// A call to an Octave function whose arguments are loaded into the workspace beforehand and
+/// whose outputs are read back out of it afterwards.
+pub(crate) struct Call<'a> {
+    function: &'a str,
+    /// The arguments, under the names they are loaded as.
+    pub(crate) inputs: Vec<(String, OctaveType)>,
+    /// The names the outputs are assigned to.
+    outputs: Vec<String>,
+}
+
+impl<'a> Call<'a> {
+    /// Prepare a call to `function` with `args` that asks for `nargout` outputs.
+    pub(crate) fn new(function: &'a str, args: &[OctaveType], nargout: usize) -> Self {
+        Call {
+            function,
+            inputs: args
+                .iter()
+                .enumerate()
+                .map(|(idx, arg)| (format!("__mocktave_input_{idx}__"), arg.clone()))
+                .collect(),
+            outputs: (0..nargout)
+                .map(|idx| format!("__mocktave_output_{idx}__"))
+                .collect(),
+        }
+    }
+
+    /// The script that calls the function once the inputs are loaded.
+    pub(crate) fn script(&self) -> String {
+        let names = self
+            .inputs
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        let mut call = self.function.to_owned() + "(" + &names.join(", ") + ");";
+        if !self.outputs.is_empty() {
+            call = "[".to_owned() + &self.outputs.join(", ") + "] = " + &call;
+        }
+        // The inputs are cleared even if the function fails, so they never show up later. A bare
+        // `clear` would take the whole workspace with it, so there is none without inputs.
+        let mut script = vec!["unwind_protect", &call, "unwind_protect_cleanup"];
+        let clear = "clear ".to_owned() + &names.join(" ");
+        if !names.is_empty() {
+            script.push(&clear);
+        }
+        script.push("end_unwind_protect");
+        script.join("\n")
+    }
+
+    /// Read the outputs out of the workspace left behind by [`Call::script`].
+    pub(crate) fn outputs<Z: Outputs>(
+        &self,
+        results: &InterpreterResults,
+    ) -> Result<Z, MocktaveError> {
+        let values = self
+            .outputs
+            .iter()
+            .map(|name| {
+                results.get(name).ok_or_else(|| MocktaveError::Parse {
+                    message: format!("`{}` did not return all of its outputs", self.function),
+                    text: results.raw.clone(),
+                    line: None,
+                    column: None,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Z::from_outputs(values))
+    }
+
+    /// The script that removes the outputs from the workspace again, if there are any.
+    pub(crate) fn cleanup(&self) -> Option<String> {
+        if self.outputs.is_empty() {
+            None
+        } else {
+            Some("clear ".to_owned() + &self.outputs.join(" "))
+        }
+    }
+}
+
+/// Take apart a script written by [`Call::script`], for backends that answer calls without
+/// running Octave. Returns the function along with the names of its inputs and its outputs.
+pub(crate) fn parse(script: &str) -> Option<(&str, Vec<&str>, Vec<&str>)> {
+    let mut lines = script.lines();
+    if lines.next()? != "unwind_protect" {
+        return None;
+    }
+    let call = lines.next()?;
+    if lines.next()? != "unwind_protect_cleanup" {
+        return None;
+    }
+    let (outputs, call) = match call.split_once("] = ") {
+        Some((outputs, call)) => (outputs.strip_prefix('[')?.split(", ").collect(), call),
+        None => (Vec::new(), call),
+    };
+    let (function, inputs) = call.strip_suffix(");")?.split_once('(')?;
+    let inputs = if inputs.is_empty() {
+        Vec::new()
+    } else {
+        inputs.split(", ").collect()
+    };
+    if !inputs.is_empty() && lines.next()? != "clear ".to_owned() + &inputs.join(" ") {
+        return None;
+    }
+    if lines.next()? != "end_unwind_protect" || lines.next().is_some() {
+        return None;
+    }
+    Some((function, inputs, outputs))
+}
@@ -0,0 +1,419 @@
+//! Recording the results of scripts to files and replaying them later, so test suites that run
+//! the same scripts on every run only need Octave when something changes.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use futures_util::future::BoxFuture;
+
+use crate::backend::{self, Backend};
+use crate::builder::InterpreterBuilder;
+use crate::{
+    writer, InterpreterResults, MocktaveError, OctaveError, OctaveType, OctaveWarning, StackFrame,
+    StructFields,
+};
+
+/// The environment variable that chooses the [`RecordMode`].
+const RECORD_VARIABLE: &str = "MOCKTAVE_RECORD";
+
+/// The variable a recording keeps everything except the workspace in.
+const METADATA: &str = "__mocktave_cassette__";
+
+/// The scripts interpreters use to clean up after calls, which can be answered without Octave.
+const CLEANUP: &str = "clear __mocktave_output_";
+
+/// When a [`Cassette`] runs scripts for real and records them, rather than replaying recordings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordMode {
+    /// Run every script and record it again, replacing existing recordings. Set
+    /// `MOCKTAVE_RECORD=all` to use this.
+    All,
+    /// Replay the scripts that were recorded before, and run and record the rest. This is the
+    /// default, or set `MOCKTAVE_RECORD=new`.
+    #[default]
+    New,
+    /// Only replay recordings, and fail with [`MocktaveError::Unmatched`] for scripts that were
+    /// never recorded. Octave is never started. Set `MOCKTAVE_RECORD=none` to use this, for
+    /// example in CI.
+    ReplayOnly,
+}
+
+impl RecordMode {
+    /// Read the mode from the `MOCKTAVE_RECORD` environment variable, which can be `all`, `new`
+    /// or `none`. Unset or empty means [`RecordMode::New`], and anything else is a
+    /// [`MocktaveError::InvalidSetting`].
+    /// ```
+    /// use mocktave::RecordMode;
+    /// std::env::set_var("MOCKTAVE_RECORD", "always");
+    /// let err = RecordMode::from_env().unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "MOCKTAVE_RECORD is set to \"always\", but must be one of all|new|none"
+    /// );
+    /// ```
+    pub fn from_env() -> Result<Self, MocktaveError> {
+        match std::env::var(RECORD_VARIABLE).as_deref() {
+            Err(_) | Ok("") | Ok("new") => Ok(RecordMode::New),
+            Ok("all") => Ok(RecordMode::All),
+            Ok("none") => Ok(RecordMode::ReplayOnly),
+            Ok(other) => Err(MocktaveError::InvalidSetting {
+                variable: RECORD_VARIABLE.to_string(),
+                value: other.to_string(),
+                accepted: ["all", "new", "none"].map(str::to_string).to_vec(),
+            }),
+        }
+    }
+}
+
+/// A backend that answers scripts from recordings in a fixtures directory, and only starts Octave
+/// to record the scripts it has no recording for. Use one through
+/// [`InterpreterBuilder::cassette`].
+///
+/// A recording is found by hashing the script together with the workspace it runs in, so the
+/// same script with different inputs is recorded separately. Each recording is a file in
+/// Octave's text save format holding the workspace afterwards, along with what the script
+/// printed and any error or warnings it raised. Anything that isn't in the workspace, like
+/// global variables or the state of the random number generator, is not part of the hash, so
+/// scripts that depend on it should set it up themselves.
+pub struct Cassette {
+    dir: PathBuf,
+    mode: RecordMode,
+    /// How to start the backend that records scripts.
+    builder: InterpreterBuilder,
+    /// The backend that records scripts, once one was needed.
+    recorder: Option<Box<dyn Backend>>,
+    /// Whether the recorder's workspace is the same as `workspace`.
+    synced: bool,
+    /// The workspace as of the last script.
+    workspace: HashMap<String, OctaveType>,
+}
+
+impl Cassette {
+    /// Keep recordings in `dir`, starting a backend from `builder` when something needs to be
+    /// recorded.
+    pub(crate) fn new(dir: PathBuf, mode: RecordMode, builder: InterpreterBuilder) -> Self {
+        Cassette {
+            dir,
+            mode,
+            builder,
+            recorder: None,
+            synced: true,
+            workspace: HashMap::new(),
+        }
+    }
+
+    /// The file the recording of `script` is kept in.
+    fn path(&self, script: &str, promoted: &[String]) -> Result<PathBuf, MocktaveError> {
+        let mut names = self.workspace.keys().collect::<Vec<_>>();
+        names.sort();
+        let mut hash = Fnv::default();
+        hash.write(script.as_bytes());
+        for identifier in promoted {
+            hash.write(b"\0");
+            hash.write(identifier.as_bytes());
+        }
+        hash.write(b"\0");
+        for name in names {
+            hash.write(writer::block(name, &self.workspace[name])?.as_bytes());
+        }
+        Ok(self.dir.join(format!("{:016x}.txt", hash.0)))
+    }
+
+    /// Run `script` for real, starting the recorder first if necessary.
+    async fn record(
+        &mut self,
+        script: &str,
+        promoted: &[String],
+    ) -> Result<InterpreterResults, MocktaveError> {
+        let recorder = match &mut self.recorder {
+            Some(recorder) => recorder,
+            None => {
+                self.synced = self.workspace.is_empty();
+                self.recorder
+                    .insert(self.builder.clone().try_build_backend().await?)
+            }
+        };
+        // Scripts that were replayed since the last recording didn't run in the recorder
+        if !self.synced {
+            recorder.eval("clear", &[]).await?;
+            let variables = self
+                .workspace
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect::<Vec<_>>();
+            recorder.load(&variables).await?;
+            self.synced = true;
+        }
+        recorder.eval(script, promoted).await
+    }
+}
+
+impl Backend for Cassette {
+    fn eval<'a>(
+        &'a mut self,
+        script: &'a str,
+        promoted: &'a [String],
+    ) -> BoxFuture<'a, Result<InterpreterResults, MocktaveError>> {
+        Box::pin(async move {
+            // Pools clear the whole workspace between leases
+            if script == "clear" {
+                self.workspace.clear();
+                self.synced = false;
+                return Ok(InterpreterResults::default());
+            }
+            if script.starts_with(CLEANUP) {
+                for name in script.split_whitespace().skip(1) {
+                    self.workspace.remove(name);
+                }
+                self.synced = false;
+                return Ok(InterpreterResults::from(self.workspace.clone()));
+            }
+
+            let path = self.path(script, promoted)?;
+            let results = match self.mode {
+                RecordMode::All => None,
+                RecordMode::New | RecordMode::ReplayOnly => replay(&path, script).await?,
+            };
+            let results = match (results, self.mode) {
+                (Some(results), _) => {
+                    self.synced = false;
+                    results
+                }
+                (None, RecordMode::ReplayOnly) => {
+                    return Err(MocktaveError::Unmatched(script.to_string()))
+                }
+                (None, _) => {
+                    let results = self.record(script, promoted).await?;
+                    save(&path, script, &results).await?;
+                    results
+                }
+            };
+            self.workspace = results.variables.clone();
+            Ok(results)
+        })
+    }
+
+    fn load<'a>(
+        &'a mut self,
+        variables: &'a [(String, OctaveType)],
+    ) -> BoxFuture<'a, Result<(), MocktaveError>> {
+        Box::pin(async move {
+            // Refuse the same values Octave would, rather than failing when they are recorded
+            backend::payload(variables)?;
+            self.workspace.extend(variables.iter().cloned());
+            self.synced = false;
+            Ok(())
+        })
+    }
+
+    fn restart(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>> {
+        Box::pin(async move {
+            self.workspace.clear();
+            if let Some(recorder) = &mut self.recorder {
+                recorder.restart().await?;
+            }
+            self.synced = true;
+            Ok(())
+        })
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>> {
+        Box::pin(async move {
+            match &mut self.recorder {
+                Some(recorder) => recorder.close().await,
+                None => Ok(()),
+            }
+        })
+    }
+}
+
+/// Read the recording at `path`, if there is one.
+async fn replay(path: &Path, script: &str) -> Result<Option<InterpreterResults>, MocktaveError> {
+    let recording = match tokio::fs::read_to_string(path).await {
+        Ok(recording) => recording,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(MocktaveError::BackendUnavailable(error.to_string())),
+    };
+    let mut results = InterpreterResults::try_from(recording)?;
+    let malformed = |text: &str| MocktaveError::Parse {
+        message: format!("Malformed recording in {}", path.display()),
+        text: text.to_string(),
+        line: None,
+        column: None,
+    };
+    let Some(OctaveType::Struct(metadata)) = results.variables.remove(METADATA) else {
+        return Err(malformed(&results.raw));
+    };
+    let string = |name: &str| text(field(&metadata, name)).ok_or_else(|| malformed(name));
+    // Two scripts with the same hash are unlikely, but would be very confusing
+    if string("script")? != script {
+        return Err(malformed(&string("script")?));
+    }
+    results.stdout = string("stdout")?;
+    results.stderr = string("stderr")?;
+    if let Some(OctaveType::Struct(error)) = field(&metadata, "error") {
+        let (message, identifier, stack) = problem(error).ok_or_else(|| malformed("error"))?;
+        results.error = Some(OctaveError {
+            message,
+            identifier,
+            stack,
+        });
+    }
+    results.warnings = match field(&metadata, "warnings") {
+        Some(OctaveType::CellArray(rows)) => rows
+            .iter()
+            .flatten()
+            .map(|warning| match warning {
+                OctaveType::Struct(warning) => {
+                    problem(warning).map(|(message, identifier, stack)| OctaveWarning {
+                        message,
+                        identifier,
+                        stack,
+                    })
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| malformed("warnings"))?,
+        _ => Vec::new(),
+    };
+    results.raw = results.try_to_save_text()?;
+    Ok(Some(results))
+}
+
+/// Record `results` at `path`.
+async fn save(
+    path: &Path,
+    script: &str,
+    results: &InterpreterResults,
+) -> Result<(), MocktaveError> {
+    let string = |value: &str| OctaveType::String(value.to_string());
+    let mut metadata = vec![
+        ("script".to_string(), string(script)),
+        ("stdout".to_string(), string(&results.stdout)),
+        ("stderr".to_string(), string(&results.stderr)),
+    ];
+    if let Some(error) = &results.error {
+        metadata.push((
+            "error".to_string(),
+            describe(&error.message, &error.identifier, &error.stack),
+        ));
+    }
+    if !results.warnings.is_empty() {
+        let warnings = results
+            .warnings
+            .iter()
+            .map(|warning| describe(&warning.message, &warning.identifier, &warning.stack))
+            .collect();
+        metadata.push((
+            "warnings".to_string(),
+            OctaveType::CellArray(vec![warnings]),
+        ));
+    }
+
+    let mut recording = InterpreterResults::from(results.variables.clone());
+    recording
+        .variables
+        .insert(METADATA.to_string(), OctaveType::Struct(metadata));
+    let unavailable = |error: std::io::Error| MocktaveError::BackendUnavailable(error.to_string());
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await.map_err(unavailable)?;
+    }
+    tokio::fs::write(path, recording.try_to_save_text()?)
+        .await
+        .map_err(unavailable)
+}
+
+/// An error or a warning as a struct.
+fn describe(message: &str, identifier: &Option<String>, stack: &[StackFrame]) -> OctaveType {
+    let stack = stack
+        .iter()
+        .map(|frame| {
+            OctaveType::Struct(vec![
+                ("name".to_string(), OctaveType::String(frame.name.clone())),
+                ("line".to_string(), OctaveType::Scalar(frame.line as f64)),
+                (
+                    "column".to_string(),
+                    OctaveType::Scalar(frame.column as f64),
+                ),
+            ])
+        })
+        .collect();
+    OctaveType::Struct(vec![
+        (
+            "message".to_string(),
+            OctaveType::String(message.to_string()),
+        ),
+        (
+            "identifier".to_string(),
+            OctaveType::String(identifier.clone().unwrap_or_default()),
+        ),
+        ("stack".to_string(), OctaveType::CellArray(vec![stack])),
+    ])
+}
+
+/// The message, identifier and stack of an error or a warning written by [`describe`].
+fn problem(fields: &StructFields) -> Option<(String, Option<String>, Vec<StackFrame>)> {
+    let number = |frame: &StructFields, name: &str| match field(frame, name)? {
+        OctaveType::Scalar(value) => Some(*value as usize),
+        _ => None,
+    };
+    let stack = match field(fields, "stack")? {
+        OctaveType::CellArray(rows) => rows
+            .iter()
+            .flatten()
+            .map(|frame| match frame {
+                OctaveType::Struct(frame) => Some(StackFrame {
+                    name: text(field(frame, "name"))?,
+                    line: number(frame, "line")?,
+                    column: number(frame, "column")?,
+                }),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?,
+        _ => Vec::new(),
+    };
+    let identifier = text(field(fields, "identifier"))?;
+    Some((
+        text(field(fields, "message"))?,
+        (!identifier.is_empty()).then_some(identifier),
+        stack,
+    ))
+}
+
+/// The value of the field `name`.
+fn field<'a>(fields: &'a StructFields, name: &str) -> Option<&'a OctaveType> {
+    fields
+        .iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, value)| value)
+}
+
+/// The contents of a string, which Octave saves as an empty matrix when it is empty.
+fn text(value: Option<&OctaveType>) -> Option<String> {
+    match value? {
+        OctaveType::String(value) => Some(value.clone()),
+        OctaveType::Matrix(empty) if empty.is_empty() => Some(String::new()),
+        OctaveType::Empty => Some(String::new()),
+        _ => None,
+    }
+}
+
+/// The 64 bit FNV-1a hash, which unlike the hashers in the standard library is guaranteed to stay
+/// the same from one Rust release to the next, so recordings are found again.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
@@ -0,0 +1,389 @@
+//! Running Octave inside a Docker container.
+
+use std::collections::HashMap;
+
+use bollard::container::LogOutput;
+use futures_util::future::BoxFuture;
+use futures_util::{StreamExt, TryStreamExt};
+
+use crate::async_interpreter::runtime;
+use crate::backend::{self, Backend};
+use crate::session::{Chunk, Session, OCTAVE_ARGS};
+use crate::{InterpreterResults, MocktaveError, OctaveType};
+
+/// The image interpreters run in unless told otherwise.
+const DEFAULT_IMAGE: &str = "gnuoctave/octave";
+
+/// The tag of [`DEFAULT_IMAGE`] interpreters run in unless told otherwise.
+const DEFAULT_TAG: &str = "8.1.0";
+
+/// How long to wait for the Docker daemon to answer, in seconds.
+const CONNECTION_TIMEOUT: u64 = 120;
+
+/// Where workspace files are uploaded to inside the container.
+const LOAD_FILE: &str = "__mocktave_load__.txt";
+
+/// When to pull the image before starting a container from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PullPolicy {
+    /// Pull the image every time, so a moving tag is always up to date.
+    #[default]
+    Always,
+    /// Only pull the image if it isn't available locally yet.
+    IfMissing,
+    /// Never pull the image, for images that were built locally. Starting fails if the image
+    /// isn't there.
+    Never,
+}
+
+/// How to reach the Docker daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) enum Connection {
+    /// Use `DOCKER_HOST` if it is set, and the local socket otherwise.
+    #[default]
+    Defaults,
+    /// Use the Unix socket at this path.
+    Socket(String),
+    /// Use an address in the same form as `DOCKER_HOST`.
+    Host(String),
+}
+
+/// Everything about the container that can be configured through the builder.
+#[derive(Debug, Clone)]
+pub(crate) struct DockerSettings {
+    pub(crate) image: String,
+    pub(crate) tag: String,
+    pub(crate) pull: PullPolicy,
+    pub(crate) name: Option<String>,
+    pub(crate) labels: HashMap<String, String>,
+    pub(crate) connection: Connection,
+}
+
+impl Default for DockerSettings {
+    fn default() -> Self {
+        DockerSettings {
+            image: DEFAULT_IMAGE.to_string(),
+            tag: DEFAULT_TAG.to_string(),
+            pull: PullPolicy::default(),
+            name: None,
+            labels: HashMap::new(),
+            connection: Connection::default(),
+        }
+    }
+}
+
+/// Runs Octave in a Docker container that lives as long as the backend does. This is the backend
+/// interpreters use unless told otherwise.
+pub struct DockerBackend {
+    docker: bollard::Docker,
+    id: String,
+    session: Session,
+    /// The process id of Octave inside the container, so it can be killed if it stops responding.
+    pid: String,
+}
+
+impl DockerBackend {
+    /// Create a container as described by `settings` and start Octave inside it.
+    pub(crate) async fn start(
+        settings: &DockerSettings,
+        env: &[(String, String)],
+        working_dir: Option<&str>,
+    ) -> Result<Self, MocktaveError> {
+        let docker = connect(&settings.connection)?;
+        let image = format!("{}:{}", settings.image, settings.tag);
+        prepare(&docker, settings, &image).await?;
+
+        let config = bollard::container::Config {
+            image: Some(image.clone()),
+            tty: Some(true),
+            env: Some(
+                env.iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect(),
+            ),
+            working_dir: working_dir.map(str::to_string),
+            labels: Some(settings.labels.clone()),
+            ..Default::default()
+        };
+        let options =
+            settings
+                .name
+                .clone()
+                .map(|name| bollard::container::CreateContainerOptions {
+                    name,
+                    platform: None,
+                });
+
+        let id = docker
+            .create_container(options, config)
+            .await
+            .map_err(unavailable)?
+            .id;
+
+        // Don't leave the container behind if Octave can't be started inside it
+        let started = match docker.start_container::<String>(&id, None).await {
+            Ok(()) => attach(&docker, &id).await,
+            Err(error) => Err(unavailable(error)),
+        };
+        match started {
+            Ok((session, pid)) => Ok(DockerBackend {
+                docker,
+                id,
+                session,
+                pid,
+            }),
+            Err(error) => {
+                let _ = remove(&docker, &id).await;
+                Err(error)
+            }
+        }
+    }
+
+    /// Kill the Octave process, for when it is stuck running a script.
+    async fn kill(&mut self) -> Result<(), MocktaveError> {
+        let exec = self
+            .docker
+            .create_exec(
+                &self.id,
+                bollard::exec::CreateExecOptions {
+                    attach_stdout: Some(true),
+                    attach_stderr: Some(true),
+                    cmd: Some(vec!["sh", "-c", &format!("kill -9 {}", self.pid)]),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(unavailable)?
+            .id;
+
+        // Wait for `kill` to exit, so Octave is gone by the time we return
+        if let bollard::exec::StartExecResults::Attached { mut output, .. } = self
+            .docker
+            .start_exec(&exec, None)
+            .await
+            .map_err(unavailable)?
+        {
+            while output.next().await.is_some() {}
+        }
+        Ok(())
+    }
+}
+
+impl Backend for DockerBackend {
+    fn eval<'a>(
+        &'a mut self,
+        script: &'a str,
+        promoted: &'a [String],
+    ) -> BoxFuture<'a, Result<InterpreterResults, MocktaveError>> {
+        Box::pin(async move { self.session.eval(script, promoted).await?.into_results() })
+    }
+
+    fn load<'a>(
+        &'a mut self,
+        variables: &'a [(String, OctaveType)],
+    ) -> BoxFuture<'a, Result<(), MocktaveError>> {
+        Box::pin(async move {
+            if variables.is_empty() {
+                return Ok(());
+            }
+            let payload = backend::payload(variables)?;
+            self.docker
+                .upload_to_container(
+                    &self.id,
+                    Some(bollard::container::UploadToContainerOptions {
+                        path: "/tmp",
+                        ..Default::default()
+                    }),
+                    tar(LOAD_FILE, payload.as_bytes()).into(),
+                )
+                .await
+                .map_err(unavailable)?;
+            let script = backend::load_script(&format!("/tmp/{LOAD_FILE}"));
+            backend::check(self.eval(&script, &[]).await?)
+        })
+    }
+
+    fn restart(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>> {
+        Box::pin(async move {
+            self.kill().await?;
+            (self.session, self.pid) = attach(&self.docker, &self.id).await?;
+            Ok(())
+        })
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>> {
+        Box::pin(async move {
+            let id = std::mem::take(&mut self.id);
+            if id.is_empty() {
+                return Ok(());
+            }
+            remove(&self.docker, &id).await
+        })
+    }
+}
+
+impl Drop for DockerBackend {
+    fn drop(&mut self) {
+        if self.id.is_empty() {
+            return;
+        }
+        let docker = self.docker.clone();
+        let id = std::mem::take(&mut self.id);
+        let remove = async move {
+            // Failing to clean up shouldn't take the rest of the program down with it.
+            let _ = remove(&docker, &id).await;
+        };
+        // Inside a runtime we can't block, so the container is removed in the background
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(remove);
+            }
+            Err(_) => {
+                if let Ok(runtime) = runtime() {
+                    runtime.block_on(remove);
+                }
+            }
+        }
+    }
+}
+
+/// Connect to the Docker daemon.
+fn connect(connection: &Connection) -> Result<bollard::Docker, MocktaveError> {
+    let version = bollard::API_DEFAULT_VERSION;
+    match connection {
+        Connection::Defaults => bollard::Docker::connect_with_defaults(),
+        Connection::Socket(path) => {
+            bollard::Docker::connect_with_socket(path, CONNECTION_TIMEOUT, version)
+        }
+        Connection::Host(host) if host.starts_with("unix://") => {
+            bollard::Docker::connect_with_socket(host, CONNECTION_TIMEOUT, version)
+        }
+        Connection::Host(host) if host.starts_with("tcp://") || host.starts_with("http://") => {
+            bollard::Docker::connect_with_http(host, CONNECTION_TIMEOUT, version)
+        }
+        Connection::Host(host) => {
+            return Err(MocktaveError::BackendUnavailable(format!(
+                "Unsupported Docker host `{host}`"
+            )))
+        }
+    }
+    .map_err(unavailable)
+}
+
+/// Make sure `image` is available, pulling it if the pull policy says so.
+async fn prepare(
+    docker: &bollard::Docker,
+    settings: &DockerSettings,
+    image: &str,
+) -> Result<(), MocktaveError> {
+    if settings.pull != PullPolicy::Always {
+        match docker.inspect_image(image).await {
+            Ok(_) => return Ok(()),
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {}
+            Err(error) => return Err(unavailable(error)),
+        }
+    }
+    if settings.pull == PullPolicy::Never {
+        return Err(MocktaveError::ImagePull(format!(
+            "The image `{image}` is not available locally and pulling is disabled"
+        )));
+    }
+    docker
+        .create_image(
+            Some(bollard::image::CreateImageOptions {
+                from_image: settings.image.as_str(),
+                tag: settings.tag.as_str(),
+                ..Default::default()
+            }),
+            None,
+            None,
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(|error| MocktaveError::ImagePull(error.to_string()))?;
+    Ok(())
+}
+
+/// Start Octave inside the running container `id`, and find out its process id.
+async fn attach(docker: &bollard::Docker, id: &str) -> Result<(Session, String), MocktaveError> {
+    let exec = docker
+        .create_exec(
+            id,
+            bollard::exec::CreateExecOptions {
+                attach_stdin: Some(true),
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                tty: Some(false),
+                cmd: Some([&["octave"], &OCTAVE_ARGS[..]].concat()),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(unavailable)?
+        .id;
+
+    if let bollard::exec::StartExecResults::Attached { output, input } =
+        docker.start_exec(&exec, None).await.map_err(unavailable)?
+    {
+        let output = output.filter_map(|item| async move {
+            match item {
+                Ok(LogOutput::StdOut { message }) => Some(Ok(Chunk::Stdout(message.to_vec()))),
+                Ok(LogOutput::StdErr { message }) => Some(Ok(Chunk::Stderr(message.to_vec()))),
+                Ok(_) => None,
+                Err(error) => Some(Err(unavailable(error))),
+            }
+        });
+        let mut session = Session::new(input, Box::pin(output)).await?;
+        let pid = session.eval("printf(\"%d\", getpid());", &[]).await?.stdout;
+        Ok((session, pid))
+    } else {
+        unreachable!();
+    }
+}
+
+/// Remove the container `id`, stopping it first if it is still running.
+async fn remove(docker: &bollard::Docker, id: &str) -> Result<(), MocktaveError> {
+    docker
+        .remove_container(
+            id,
+            Some(bollard::container::RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await
+        .map_err(unavailable)
+}
+
+/// Pack a single file into a tar archive, which is what the Docker API expects for uploads.
+fn tar(name: &str, contents: &[u8]) -> Vec<u8> {
+    let mut header = [0_u8; 512];
+    let mut field = |offset: usize, value: &[u8]| {
+        header[offset..offset + value.len()].copy_from_slice(value);
+    };
+    field(0, name.as_bytes());
+    field(100, b"0000644\0");
+    field(108, b"0000000\0");
+    field(116, b"0000000\0");
+    field(124, format!("{:011o}\0", contents.len()).as_bytes());
+    field(136, b"00000000000\0");
+    field(148, b"        ");
+    field(156, b"0");
+    field(257, b"ustar\x0000");
+    // The checksum is the sum of the header bytes, counting its own field as spaces
+    let checksum = header.iter().map(|byte| *byte as u32).sum::<u32>();
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+
+    let mut archive = header.to_vec();
+    archive.extend_from_slice(contents);
+    archive.resize(archive.len().div_ceil(512) * 512, 0);
+    // Two empty blocks mark the end of the archive
+    archive.resize(archive.len() + 1024, 0);
+    archive
+}
+
+fn unavailable(error: bollard::errors::Error) -> MocktaveError {
+    MocktaveError::BackendUnavailable(error.to_string())
+}
@@ -0,0 +1,220 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// Everything that can go wrong while running Octave code through this library.
+/// ```
+/// use mocktave::MocktaveError;
+/// let error = MocktaveError::Octave {
+///     message: "boom".to_string(),
+///     line: Some(3),
+///     identifier: Some("mocktave:demo".to_string()),
+///     stack: Vec::new(),
+/// };
+/// assert_eq!(error.to_string(), "Octave error on line 3: boom");
+/// ```
+/// More variants may be added, so matches need a wildcard arm.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum MocktaveError {
+    /// The backend that runs Octave could not be reached, or stopped responding. For the Docker
+    /// backend this usually means the Docker daemon is not running.
+    BackendUnavailable(String),
+    /// The image containing Octave could not be pulled.
+    ImagePull(String),
+    /// Octave itself raised an error while running the code.
+    Octave {
+        /// The error message reported by Octave.
+        message: String,
+        /// The line the error was raised on, if Octave reported one.
+        line: Option<usize>,
+        /// The error identifier, e.g. `Octave:undefined-function`, if the error has one.
+        identifier: Option<String>,
+        /// Where the error was raised, innermost frame first.
+        stack: Vec<StackFrame>,
+    },
+    /// The output from Octave could not be understood.
+    Parse {
+        /// What went wrong.
+        message: String,
+        /// The piece of output that could not be parsed.
+        text: String,
+        /// The line of the output it was found on, counting from one, if it is known.
+        line: Option<usize>,
+        /// The column within that line, counting from one, if it is known.
+        column: Option<usize>,
+    },
+    /// Octave did not finish running the code in time.
+    Timeout(Duration),
+    /// A backend that only knows the answers to the scripts it was prepared for, like
+    /// [`FakeBackend`](crate::FakeBackend), was asked to run some other script.
+    Unmatched(String),
+    /// A value can't be written out for Octave, like a struct array whose elements don't all
+    /// have the same fields.
+    InvalidValue(String),
+    /// An environment variable that configures this library is set to a value it doesn't
+    /// understand.
+    InvalidSetting {
+        /// The name of the environment variable.
+        variable: String,
+        /// The value it is set to.
+        value: String,
+        /// The values it may be set to.
+        accepted: Vec<String>,
+    },
+}
+
+impl Display for MocktaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MocktaveError::BackendUnavailable(message) => {
+                write!(f, "Octave backend unavailable: {message}")
+            }
+            MocktaveError::ImagePull(message) => write!(f, "Could not pull image: {message}"),
+            MocktaveError::Octave {
+                message,
+                line: Some(line),
+                ..
+            } => write!(f, "Octave error on line {line}: {message}"),
+            MocktaveError::Octave {
+                message,
+                line: None,
+                ..
+            } => write!(f, "Octave error: {message}"),
+            MocktaveError::Parse {
+                message,
+                text,
+                line: Some(line),
+                column: Some(column),
+            } => write!(
+                f,
+                "Could not parse Octave output at line {line}, column {column} ({message}): {text:?}"
+            ),
+            MocktaveError::Parse { message, text, .. } => {
+                write!(f, "Could not parse Octave output ({message}): {text:?}")
+            }
+            MocktaveError::Timeout(duration) => {
+                write!(f, "Octave did not finish within {duration:?}")
+            }
+            MocktaveError::Unmatched(script) => {
+                write!(f, "No response was prepared for the script {script:?}")
+            }
+            MocktaveError::InvalidValue(message) => write!(f, "Invalid value: {message}"),
+            MocktaveError::InvalidSetting {
+                variable,
+                value,
+                accepted,
+            } => write!(
+                f,
+                "{variable} is set to {value:?}, but must be one of {}",
+                accepted.join("|")
+            ),
+        }
+    }
+}
+
+impl Error for MocktaveError {}
+
+/// An error raised by Octave while running a script, as reported by `try`/`catch`.
+/// ```
+/// let res = mocktave::eval("x = 1; error('mocktave:demo', 'boom')");
+/// let error = res.error().unwrap();
+/// assert_eq!(error.message, "boom");
+/// assert_eq!(error.identifier.as_deref(), Some("mocktave:demo"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OctaveError {
+    /// The error message.
+    pub message: String,
+    /// The error identifier, e.g. `Octave:undefined-function`, if the error has one.
+    pub identifier: Option<String>,
+    /// Where the error was raised, innermost frame first.
+    pub stack: Vec<StackFrame>,
+}
+
+/// A warning raised by Octave while running a script, like
+/// `warning: matrix singular to machine precision`.
+/// ```
+/// let res = mocktave::eval("x = inv([1 2; 2 4]);");
+/// let warning = &res.warnings()[0];
+/// assert_eq!(warning.message, "matrix singular to machine precision");
+/// assert_eq!(warning.identifier.as_deref(), Some("Octave:singular-matrix"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OctaveWarning {
+    /// The warning message.
+    pub message: String,
+    /// The warning identifier, e.g. `Octave:singular-matrix`. Octave only remembers the identifier
+    /// of the most recent warning, so this is `None` for any earlier warnings in the same script.
+    pub identifier: Option<String>,
+    /// Where the warning was raised, innermost frame first. Octave only reports this for warnings
+    /// raised inside functions.
+    pub stack: Vec<StackFrame>,
+}
+
+/// A single entry in the stack of an [`OctaveError`] or an [`OctaveWarning`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StackFrame {
+    /// The name of the function the error or warning passed through.
+    pub name: String,
+    /// The line within that function.
+    pub line: usize,
+    /// The column within that line.
+    pub column: usize,
+}
+
+impl Display for OctaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error: {}", self.message)?;
+        for frame in &self.stack {
+            write!(
+                f,
+                "\n    {} at line {} column {}",
+                frame.name, frame.line, frame.column
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for OctaveError {}
+
+impl Display for OctaveWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "warning: {}", self.message)?;
+        for frame in &self.stack {
+            write!(
+                f,
+                "\n    {} at line {} column {}",
+                frame.name, frame.line, frame.column
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Keep everything Octave reported about an error
+/// ```
+/// use mocktave::{MocktaveError, OctaveError, StackFrame};
+/// let frame = StackFrame { name: "f".to_string(), line: 2, column: 5 };
+/// let error = MocktaveError::from(OctaveError {
+///     message: "boom".to_string(),
+///     identifier: Some("mocktave:demo".to_string()),
+///     stack: vec![frame.clone()],
+/// });
+/// assert!(matches!(
+///     error,
+///     MocktaveError::Octave { line: Some(2), identifier: Some(id), stack, .. }
+///         if id == "mocktave:demo" && stack == vec![frame]
+/// ));
+/// ```
+impl From<OctaveError> for MocktaveError {
+    fn from(error: OctaveError) -> Self {
+        MocktaveError::Octave {
+            line: error.stack.first().map(|frame| frame.line),
+            message: error.message,
+            identifier: error.identifier,
+            stack: error.stack,
+        }
+    }
+}
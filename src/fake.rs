@@ -0,0 +1,225 @@
+//! A stand-in for Octave that answers with prepared results, so code built on this library can be
+//! tested without Octave or Docker.
+
+use std::collections::HashMap;
+
+use futures_util::future::BoxFuture;
+
+use crate::backend::{self, Backend};
+use crate::call;
+use crate::{InterpreterResults, MocktaveError, OctaveError, OctaveType};
+
+/// Answers a script given the workspace it would run in, or passes on it with `None`.
+type Respond =
+    Box<dyn FnMut(&str, &HashMap<String, OctaveType>) -> Option<InterpreterResults> + Send>;
+
+/// Computes the outputs of a function from its arguments.
+type Function = Box<dyn FnMut(&[OctaveType]) -> Vec<OctaveType> + Send>;
+
+/// A backend that never runs Octave, and instead answers every script with results prepared in
+/// advance. It is meant for unit testing code that uses an [`Interpreter`] deterministically and
+/// offline. Hand it to [`Interpreter::with_backend`].
+///
+/// Like Octave, it keeps a workspace. Variables created with [`Interpreter::set`], the outputs
+/// of function calls and the variables in prepared results are added to it, and the results of
+/// every script report the whole workspace. Scripts nobody prepared an answer for fail with
+/// [`MocktaveError::Unmatched`].
+/// ```
+/// use mocktave::{FakeBackend, Interpreter, InterpreterResults, OctaveType};
+/// let fake = FakeBackend::new()
+///     .on_script("x = mean(data)", InterpreterResults::default().with_variable("x", 2.0))
+///     .on_call("norm", |_args| vec![OctaveType::Scalar(5.0)]);
+/// let mut interp = Interpreter::with_backend(fake);
+///
+/// interp.set("data", vec![1.0, 2.0, 3.0]);
+/// let res = interp.eval("x = mean(data)");
+/// assert_eq!(res.get_scalar("x"), Some(2.0));
+/// assert_eq!(res.get_matrix("data").unwrap(), vec![vec![1.0, 2.0, 3.0]]);
+///
+/// let norm: f64 = interp.call("norm", &[vec![3.0, 4.0].into()]);
+/// assert_eq!(norm, 5.0);
+///
+/// let err = interp.try_eval("y = 1").unwrap_err();
+/// assert_eq!(err, mocktave::MocktaveError::Unmatched("y = 1".to_string()));
+/// ```
+///
+/// [`Interpreter`]: crate::Interpreter
+/// [`Interpreter::with_backend`]: crate::Interpreter::with_backend
+/// [`Interpreter::set`]: crate::Interpreter::set
+#[derive(Default)]
+pub struct FakeBackend {
+    responses: Vec<Respond>,
+    functions: HashMap<String, Function>,
+    workspace: HashMap<String, OctaveType>,
+}
+
+impl FakeBackend {
+    /// A backend that doesn't know the answer to any script yet.
+    pub fn new() -> Self {
+        FakeBackend::default()
+    }
+
+    /// Answer `script` with `results`. Whitespace around the script is ignored.
+    pub fn on_script(self, script: &str, results: InterpreterResults) -> Self {
+        let script = script.trim().to_string();
+        self.on(move |candidate, _| (candidate.trim() == script).then(|| results.clone()))
+    }
+
+    /// Answer scripts with `respond`, which gets the script and the current workspace and returns
+    /// `None` for scripts it doesn't want to answer. Answers are looked for in the order they were
+    /// added, so this can also serve as a fallback after more specific ones.
+    /// ```
+    /// use mocktave::{FakeBackend, Interpreter, InterpreterResults, OctaveError};
+    /// let fake = FakeBackend::new().on(|script, _workspace| {
+    ///     if !script.contains("chol(") {
+    ///         return None;
+    ///     }
+    ///     Some(InterpreterResults::default().with_error(OctaveError {
+    ///         message: "chol: input matrix must be positive definite".to_string(),
+    ///         ..Default::default()
+    ///     }))
+    /// });
+    /// let mut interp = Interpreter::with_backend(fake);
+    /// let res = interp.eval("r = chol(-eye(2))");
+    /// assert!(res.error().unwrap().message.starts_with("chol:"));
+    /// ```
+    pub fn on(
+        mut self,
+        respond: impl FnMut(&str, &HashMap<String, OctaveType>) -> Option<InterpreterResults>
+            + Send
+            + 'static,
+    ) -> Self {
+        self.responses.push(Box::new(respond));
+        self
+    }
+
+    /// Answer calls to the Octave function `function` made through [`Interpreter::call`] and
+    /// its relatives by passing the arguments to `function_impl`, which returns the outputs.
+    /// Any outputs beyond the number that was asked for are ignored.
+    /// ```
+    /// use mocktave::{FakeBackend, Interpreter, OctaveType};
+    /// let fake = FakeBackend::new().on_call("size", |args| match &args[0] {
+    ///     OctaveType::Matrix(rows) => vec![
+    ///         OctaveType::Scalar(rows.len() as f64),
+    ///         OctaveType::Scalar(rows[0].len() as f64),
+    ///     ],
+    ///     _ => vec![OctaveType::Scalar(1.0), OctaveType::Scalar(1.0)],
+    /// });
+    /// let mut interp = Interpreter::with_backend(fake);
+    /// let (rows, cols): (f64, f64) = interp.call_n("size", &[vec![vec![0.0; 3]; 2].into()]);
+    /// assert_eq!((rows, cols), (2.0, 3.0));
+    /// ```
+    ///
+    /// [`Interpreter::call`]: crate::Interpreter::call
+    pub fn on_call(
+        mut self,
+        function: &str,
+        function_impl: impl FnMut(&[OctaveType]) -> Vec<OctaveType> + Send + 'static,
+    ) -> Self {
+        self.functions
+            .insert(function.to_string(), Box::new(function_impl));
+        self
+    }
+
+    /// Find the answer to `script`.
+    fn respond(&mut self, script: &str) -> Result<InterpreterResults, MocktaveError> {
+        if let Some((function, inputs, outputs)) = call::parse(script) {
+            return self.call(script, function, &inputs, &outputs);
+        }
+        // This is how interpreters clean up after calls, and `clear` on its own clears everything
+        if script == "clear" {
+            self.workspace.clear();
+            return Ok(InterpreterResults::default());
+        }
+        if let Some(names) = script.strip_prefix("clear ") {
+            for name in names.split_whitespace() {
+                self.workspace.remove(name);
+            }
+            return Ok(InterpreterResults::default());
+        }
+
+        self.responses
+            .iter_mut()
+            .find_map(|respond| respond(script, &self.workspace))
+            .ok_or_else(|| MocktaveError::Unmatched(script.to_string()))
+    }
+
+    /// Answer a call to `function` with the arguments stored under `inputs`, storing its results
+    /// under `outputs`.
+    fn call(
+        &mut self,
+        script: &str,
+        function: &str,
+        inputs: &[&str],
+        outputs: &[&str],
+    ) -> Result<InterpreterResults, MocktaveError> {
+        let Some(function) = self.functions.get_mut(function) else {
+            return Err(MocktaveError::Unmatched(script.to_string()));
+        };
+        let args = inputs
+            .iter()
+            .filter_map(|name| self.workspace.remove(*name))
+            .collect::<Vec<_>>();
+        for (name, value) in outputs.iter().zip(function(&args)) {
+            self.workspace.insert(name.to_string(), value);
+        }
+        Ok(InterpreterResults::default())
+    }
+}
+
+impl Backend for FakeBackend {
+    fn eval<'a>(
+        &'a mut self,
+        script: &'a str,
+        promoted: &'a [String],
+    ) -> BoxFuture<'a, Result<InterpreterResults, MocktaveError>> {
+        Box::pin(async move {
+            let mut results = self.respond(script)?;
+
+            // A promoted warning stops the script, just like an error
+            let promoted = results.warnings.iter().position(|warning| {
+                warning
+                    .identifier
+                    .as_ref()
+                    .is_some_and(|identifier| promoted.contains(identifier))
+            });
+            if let (Some(index), None) = (promoted, &results.error) {
+                let warning = results.warnings.remove(index);
+                results.warnings.truncate(index);
+                results.error = Some(OctaveError {
+                    message: warning.message,
+                    identifier: warning.identifier,
+                    stack: warning.stack,
+                });
+            }
+
+            self.workspace.extend(results.variables.drain());
+            results.variables = self.workspace.clone();
+            results.raw = results.try_to_save_text()?;
+            Ok(results)
+        })
+    }
+
+    fn load<'a>(
+        &'a mut self,
+        variables: &'a [(String, OctaveType)],
+    ) -> BoxFuture<'a, Result<(), MocktaveError>> {
+        Box::pin(async move {
+            // Refuse the same values a real backend would
+            backend::payload(variables)?;
+            self.workspace.extend(variables.iter().cloned());
+            Ok(())
+        })
+    }
+
+    fn restart(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>> {
+        Box::pin(async move {
+            self.workspace.clear();
+            Ok(())
+        })
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>> {
+        Box::pin(async move { Ok(()) })
+    }
+}
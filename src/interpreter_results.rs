@@ -1,26 +1,27 @@
-use std::error::Error;
+use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
-use std::num::ParseFloatError;
 use std::ops::{Index, IndexMut};
-use std::{collections::HashMap, str::FromStr};
 
-use regex::{Captures, Match};
-
-use crate::OctaveType;
-
-use crate::octave_types::OctaveTryIntoError;
-use human_regex::{
-    any, beginning, digit, end, exactly, multi_line_mode, named_capture, one_or_more, or,
-    printable, text, whitespace, word, zero_or_more, zero_or_one,
+use crate::{reader, writer};
+use crate::{
+    MocktaveError, NdArray, OctaveError, OctaveType, OctaveWarning, SparseParts, StructFields,
 };
 
 /// Contains the workspace that resulted from running the octave command in `eval`
 #[derive(Clone)]
 pub struct InterpreterResults {
-    /// Raw output
+    /// The workspace in Octave's text save format, exactly as it was parsed
     pub raw: String,
     /// Variables
-    variables: HashMap<String, OctaveType>,
+    pub(crate) variables: HashMap<String, OctaveType>,
+    /// Whatever the script printed to stdout
+    pub(crate) stdout: String,
+    /// Whatever the script printed to stderr
+    pub(crate) stderr: String,
+    /// The error raised by the script, if any
+    pub(crate) error: Option<OctaveError>,
+    /// The warnings raised by the script
+    pub(crate) warnings: Vec<OctaveWarning>,
 }
 
 impl InterpreterResults {
@@ -50,6 +51,85 @@ impl InterpreterResults {
             .cloned()
             .and_then(|ot| ot.try_into_vec_f64().ok())
     }
+    /// Get a variable by name and convert it to a `Vec<Vec<(f64, f64)>>`, if the variable exists and
+    /// is convertible.
+    pub fn get_complex_matrix(&self, name: &str) -> Option<Vec<Vec<(f64, f64)>>> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_vec_tuple_f64().ok())
+    }
+    /// Get a variable by name and convert it to a `bool`, if the variable exists and is convertible.
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_bool().ok())
+    }
+    /// Get a variable by name and convert it to a `Vec<Vec<bool>>`, if the variable exists and is
+    /// convertible.
+    pub fn get_bool_matrix(&self, name: &str) -> Option<Vec<Vec<bool>>> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_vec_bool().ok())
+    }
+    /// Get a variable by name and convert it to an `i128`, if the variable exists and is one of
+    /// Octave's integer classes.
+    pub fn get_integer(&self, name: &str) -> Option<i128> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_i128().ok())
+    }
+    /// Get a variable by name and convert it to a `Vec<Vec<i128>>`, if the variable exists and is
+    /// a matrix of one of Octave's integer classes.
+    pub fn get_integer_matrix(&self, name: &str) -> Option<Vec<Vec<i128>>> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_vec_i128().ok())
+    }
+    /// Get a variable by name and convert it to an `NdArray<f64>`, if the variable exists and is an
+    /// array with more than two dimensions or a matrix without rows.
+    pub fn get_nd_array(&self, name: &str) -> Option<NdArray<f64>> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_nd_f64().ok())
+    }
+    /// Get a variable by name and convert it to an `NdArray<(f64, f64)>`, if the variable exists
+    /// and is a complex array with more than two dimensions or a complex matrix without rows.
+    pub fn get_complex_nd_array(&self, name: &str) -> Option<NdArray<(f64, f64)>> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_nd_tuple_f64().ok())
+    }
+    /// Get a variable by name and convert it to an `NdArray<bool>`, if the variable exists and is a
+    /// logical array with more than two dimensions or a logical matrix without rows.
+    pub fn get_bool_nd_array(&self, name: &str) -> Option<NdArray<bool>> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_nd_bool().ok())
+    }
+    /// Get a variable by name and convert it to an `NdArray<i128>`, if the variable exists and is
+    /// an integer array with more than two dimensions or an integer matrix without rows.
+    pub fn get_integer_nd_array(&self, name: &str) -> Option<NdArray<i128>> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_nd_i128().ok())
+    }
+    /// Get a variable by name and convert it to its number of rows, number of columns and nonzero
+    /// elements, if the variable exists and is a sparse matrix.
+    pub fn get_sparse_matrix(&self, name: &str) -> Option<SparseParts<f64>> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_sparse_f64().ok())
+    }
     /// Get a variable by name and convert it to a `String`, if the variable exists and is convertible.
     pub fn get_string(&self, name: &str) -> Option<String> {
         self.variables
@@ -65,13 +145,199 @@ impl InterpreterResults {
             .cloned()
             .and_then(|ot| ot.try_into_vec_octave_type().ok())
     }
+    /// Get a variable by name and convert it to the `StructFields` of the struct, if the
+    /// variable exists and is a struct.
+    pub fn get_struct(&self, name: &str) -> Option<StructFields> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_struct().ok())
+    }
+    /// Get a variable by name and convert it to a `Vec<Vec<StructFields>>`, if the
+    /// variable exists and is a struct array.
+    pub fn get_struct_array(&self, name: &str) -> Option<Vec<Vec<StructFields>>> {
+        self.variables
+            .get(name)
+            .cloned()
+            .and_then(|ot| ot.try_into_struct_array().ok())
+    }
+    /// Get a value nested inside structs, struct arrays and cell arrays using a path like
+    /// `s.opts.tol` or `s(2).name`, where indices are one-based like they are in Octave.
+    /// ```
+    /// use mocktave::{InterpreterResults, OctaveType};
+    /// let results = InterpreterResults::try_from(concat!(
+    ///     "# name: s\n# type: scalar struct\n# ndims: 2\n 1 1\n# length: 1\n",
+    ///     "# name: opts\n# type: scalar struct\n# ndims: 2\n 1 1\n# length: 1\n",
+    ///     "# name: tol\n# type: scalar\n0.001\n\n\n\n\n",
+    /// ).to_string()).unwrap();
+    /// assert_eq!(results.get_path("s.opts.tol"), Some(OctaveType::Scalar(0.001)));
+    /// assert_eq!(results.get_path("s.opts.maxit"), None);
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<OctaveType> {
+        let mut value: Option<OctaveType> = None;
+        for segment in path.split('.') {
+            let (name, index) = match segment.split_once('(') {
+                Some((name, index)) => (name, Some(index.strip_suffix(')')?.trim().parse().ok()?)),
+                None => (segment, None),
+            };
+            let next = match &value {
+                None => self.variables.get(name)?,
+                Some(value) => value.field(name)?,
+            }
+            .clone();
+            value = Some(match index {
+                Some(index) => next.element(index)?,
+                None => next,
+            });
+        }
+        value
+    }
     /// Get a variable without checking whether or not it exists first. Panics if variable doesn't
     /// exist.
     pub fn get_unchecked(&self, name: &str) -> OctaveType {
         self.variables
             .get(name)
             .cloned()
-            .expect(&format!("The variable `{name}` does not exist"))
+            .unwrap_or_else(|| match &self.error {
+                Some(error) => panic!(
+                    "The variable `{name}` does not exist because the script failed:\n{error}"
+                ),
+                None => panic!("The variable `{name}` does not exist"),
+            })
+    }
+    /// Get the error raised while running the script, if there was one. A variable that is
+    /// missing because the script crashed before defining it can be told apart from one that was
+    /// never defined by checking this.
+    /// ```
+    /// let res = mocktave::eval("a = 1");
+    /// assert!(res.error().is_none());
+    /// let res = mocktave::eval("a = undefined_function_for_sure(1)");
+    /// let error = res.error().unwrap();
+    /// assert_eq!(error.identifier.as_deref(), Some("Octave:undefined-function"));
+    /// ```
+    pub fn error(&self) -> Option<&OctaveError> {
+        self.error.as_ref()
+    }
+    /// Get the warnings raised while running the script, in the order they were raised.
+    /// ```
+    /// let res = mocktave::eval("warning('first'); warning('mocktave:demo', 'second');");
+    /// let messages = res.warnings().iter().map(|w| w.message.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(messages, ["first", "second"]);
+    /// assert_eq!(res.warnings()[1].identifier.as_deref(), Some("mocktave:demo"));
+    /// ```
+    pub fn warnings(&self) -> &[OctaveWarning] {
+        &self.warnings
+    }
+    /// Get everything the script printed to stdout. The workspace is kept separately, so this is
+    /// only what the script itself wrote.
+    /// ```
+    /// let res = mocktave::eval("disp(42); a = 1;");
+    /// assert_eq!(res.stdout(), "42\n");
+    /// ```
+    pub fn stdout(&self) -> &str {
+        &self.stdout
+    }
+    /// Get everything the script printed to stderr, including the message of any error it raised.
+    /// ```
+    /// let res = mocktave::eval("fprintf(stderr, 'careful\\n');");
+    /// assert_eq!(res.stderr(), "careful\n");
+    /// assert_eq!(res.stdout(), "");
+    /// ```
+    pub fn stderr(&self) -> &str {
+        &self.stderr
+    }
+    /// Get all of the variables in the workspace.
+    /// ```
+    /// let res = mocktave::eval("a = 1; b = 'two';");
+    /// let mut names = res.workspace().keys().collect::<Vec<_>>();
+    /// names.sort();
+    /// assert_eq!(names, ["a", "b"]);
+    /// ```
+    pub fn workspace(&self) -> &HashMap<String, OctaveType> {
+        &self.variables
+    }
+    /// Add the variable `name` with the value `value`, for example to prepare the results a
+    /// [`FakeBackend`](crate::FakeBackend) responds with.
+    /// ```
+    /// use mocktave::InterpreterResults;
+    /// let res = InterpreterResults::default()
+    ///     .with_variable("a", 1.0)
+    ///     .with_variable("s", "text");
+    /// assert_eq!(res.get_scalar("a"), Some(1.0));
+    /// assert_eq!(res.get_string("s").unwrap(), "text");
+    /// ```
+    pub fn with_variable(mut self, name: &str, value: impl Into<OctaveType>) -> Self {
+        self.variables.insert(name.to_string(), value.into());
+        self
+    }
+    /// Set what the script printed to stdout.
+    pub fn with_stdout(mut self, stdout: &str) -> Self {
+        self.stdout = stdout.to_string();
+        self
+    }
+    /// Set what the script printed to stderr.
+    pub fn with_stderr(mut self, stderr: &str) -> Self {
+        self.stderr = stderr.to_string();
+        self
+    }
+    /// Set the error the script raised.
+    /// ```
+    /// use mocktave::{InterpreterResults, OctaveError};
+    /// let res = InterpreterResults::default().with_error(OctaveError {
+    ///     message: "boom".to_string(),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(res.error().unwrap().message, "boom");
+    /// ```
+    pub fn with_error(mut self, error: OctaveError) -> Self {
+        self.error = Some(error);
+        self
+    }
+    /// Add a warning the script raised, after any that were added before.
+    pub fn with_warning(mut self, warning: OctaveWarning) -> Self {
+        self.warnings.push(warning);
+        self
+    }
+    /// Write the variables in Octave's text save format, as `save -text` would. The result can be
+    /// read back with [`InterpreterResults::try_from`] or loaded into Octave with `load`.
+    /// ```
+    /// use mocktave::{InterpreterResults, OctaveType};
+    /// use std::collections::HashMap;
+    /// let workspace = InterpreterResults::from(HashMap::from([
+    ///     ("a".to_string(), OctaveType::Scalar(0.1)),
+    ///     ("b".to_string(), OctaveType::String("it's".to_string())),
+    /// ]));
+    /// let text = workspace.to_save_text();
+    /// assert!(text.contains("# name: a\n# type: scalar\n0.1\n"));
+    ///
+    /// let results = InterpreterResults::try_from(text).unwrap();
+    /// assert_eq!(results.get_scalar("a"), Some(0.1));
+    /// assert_eq!(results.get_string("b").unwrap(), "it's");
+    /// ```
+    /// Panics if a variable can't be written, see [`InterpreterResults::try_to_save_text`].
+    pub fn to_save_text(&self) -> String {
+        self.try_to_save_text()
+            .expect("Could not write the workspace.")
+    }
+    /// Write the variables in Octave's text save format like
+    /// [`InterpreterResults::to_save_text`], but report values Octave can't hold instead of
+    /// panicking, like struct arrays whose elements have different fields.
+    /// ```
+    /// use mocktave::{InterpreterResults, MocktaveError, OctaveType};
+    /// let element = |name: &str| vec![(name.to_string(), OctaveType::Scalar(1.0))];
+    /// let workspace = InterpreterResults::default()
+    ///     .with_variable("s", OctaveType::StructArray(vec![vec![element("a"), element("b")]]));
+    /// let error = workspace.try_to_save_text().unwrap_err();
+    /// assert!(matches!(error, MocktaveError::InvalidValue(_)));
+    /// ```
+    pub fn try_to_save_text(&self) -> Result<String, MocktaveError> {
+        let mut names = self.variables.keys().collect::<Vec<&String>>();
+        names.sort();
+        let mut text = format!("# Created by mocktave {}\n", env!("CARGO_PKG_VERSION"));
+        for name in names {
+            text += &writer::block(name, &self.variables[name])?;
+        }
+        Ok(text)
     }
 }
 impl Default for InterpreterResults {
@@ -79,13 +345,17 @@ impl Default for InterpreterResults {
         InterpreterResults {
             raw: "".to_string(),
             variables: Default::default(),
+            stdout: "".to_string(),
+            stderr: "".to_string(),
+            error: None,
+            warnings: Vec::new(),
         }
     }
 }
 
 impl Display for InterpreterResults {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut keys = &mut self
+        let keys = &mut self
             .variables
             .keys()
             .cloned()
@@ -106,121 +376,55 @@ impl Debug for InterpreterResults {
     }
 }
 
-impl From<String> for InterpreterResults {
-    fn from(output: String) -> Self {
-        // Instantiate results and save raw output
-        let mut results = InterpreterResults {
-            raw: output.clone(),
+/// Collect variables into a workspace, for example to write them out with
+/// [`InterpreterResults::to_save_text`]
+/// ```
+/// use mocktave::{InterpreterResults, OctaveType};
+/// use std::collections::HashMap;
+/// let workspace = InterpreterResults::from(HashMap::from([("a".to_string(), OctaveType::Scalar(1.0))]));
+/// assert_eq!(workspace.get_scalar("a"), Some(1.0));
+/// ```
+impl From<HashMap<String, OctaveType>> for InterpreterResults {
+    fn from(variables: HashMap<String, OctaveType>) -> Self {
+        InterpreterResults {
+            variables,
             ..Default::default()
-        };
-
-        // Make a scalar match and parse the output
-        let scalar_match = multi_line_mode(
-            beginning()
-                + text("# name: ")
-                + named_capture(one_or_more(word()), "name")
-                + text("\n# type: scalar\n")
-                + named_capture(exactly(1, beginning() + one_or_more(any()) + end()), "data"),
-        );
-
-        for capture in scalar_match.to_regex().captures_iter(&output) {
-            let (name, value) = parse_scalar_capture(capture);
-            results.variables.insert(name, OctaveType::Scalar(value));
-        }
-
-        // Make a complex scalar match and parse the output
-        let complex_scalar_match = multi_line_mode(
-            beginning()
-                + text("# name: ")
-                + named_capture(one_or_more(word()), "name")
-                + text("\n# type: complex scalar\n")
-                + named_capture(exactly(1, beginning() + one_or_more(any()) + end()), "data"),
-        );
-
-        for capture in complex_scalar_match.to_regex().captures_iter(&output) {
-            let (name, im, re) = parse_complex_scalar_capture(capture);
-            results
-                .variables
-                .insert(name, OctaveType::ComplexScalar(im, re));
-        }
-
-        // Make a string capture and parse the output
-        let string_match = multi_line_mode(
-            beginning()
-                + text("# name: ")
-                + named_capture(one_or_more(word()), "name")
-                + or(&[text("\n# type: sq_string"), text("\n# type: string")])
-                + text("\n# elements: ")
-                + named_capture(one_or_more(digit()), "elements")
-                + text("\n# length: ")
-                + named_capture(one_or_more(digit()), "length")
-                + text("\n")
-                + named_capture(exactly(1, beginning() + one_or_more(any()) + end()), "data"),
-        );
-
-        for capture in string_match.to_regex().captures_iter(&output) {
-            let (name, value) = parse_string_capture(capture);
-            results.variables.insert(name, OctaveType::String(value));
         }
+    }
+}
 
-        // Make a matrix match and parse the output
-        let matrix_match = multi_line_mode(
-            beginning()
-                + text("# name: ")
-                + named_capture(one_or_more(word()), "name")
-                + or(&[text("\n# type: matrix"), text("\n# type: diagonal matrix")])
-                + text("\n# rows: ")
-                + named_capture(one_or_more(digit()), "rows")
-                + text("\n# columns: ")
-                + named_capture(one_or_more(digit()), "columns")
-                + text("\n")
-                + named_capture(zero_or_more(one_or_more(printable()) + text("\n")), "data"),
-        );
-
-        for capture in matrix_match.to_regex().captures_iter(&output) {
-            let (name, value) = parse_matrix_capture(capture);
-            results.variables.insert(name, OctaveType::Matrix(value));
-        }
-
-        // Make a cell-array match and parse the output
-        let cell_array_match = multi_line_mode(
-            beginning()
-                + text("# name: ")
-                + named_capture(one_or_more(word()), "name")
-                + text("\n# type: cell")
-                + text("\n# rows: ")
-                + named_capture(one_or_more(digit()), "rows")
-                + text("\n# columns: ")
-                + named_capture(one_or_more(digit()), "columns"),
-        );
-
-        let cell_element_match = multi_line_mode(
-            beginning()
-                + named_capture(text("# name: <cell-element>\n"), "name")
-                + text("# type: ")
-                + named_capture(one_or_more(word()), "type")
-                + zero_or_more(text("\n# rows: ") + named_capture(one_or_more(digit()), "rows"))
-                + zero_or_more(
-                    text("\n# columns: ") + named_capture(one_or_more(digit()), "columns"),
-                )
-                + zero_or_more(
-                    text("\n# elements: ") + named_capture(one_or_more(digit()), "elements"),
-                )
-                + zero_or_more(
-                    text("\n# length: ") + named_capture(one_or_more(digit()), "length"),
-                )
-                + zero_or_one(named_capture(
-                    one_or_more(whitespace() + beginning() + one_or_more(any())),
-                    "data",
-                )),
-        );
-
-        results.variables.extend(parse_cell_array_capture(
-            cell_array_match.to_regex().captures_iter(&output),
-            cell_element_match.to_regex().captures_iter(&output),
-        ));
-
-        results
+/// Parse the output of Octave's `save` command, reporting anything that cannot be parsed
+/// instead of panicking.
+/// ```
+/// use mocktave::InterpreterResults;
+/// let results = InterpreterResults::try_from(
+///     "# name: a\n# type: scalar\n7\n".to_string()
+/// ).unwrap();
+/// assert_eq!(results.get_scalar("a"), Some(7.0));
+/// ```
+/// ```
+/// use mocktave::{InterpreterResults, MocktaveError};
+/// let error = InterpreterResults::try_from(
+///     "# name: a\n# type: scalar\nseven\n".to_string()
+/// ).unwrap_err();
+/// assert!(matches!(error, MocktaveError::Parse { .. }));
+/// ```
+/// Generic code gets the same error through `try_into`
+/// ```
+/// use mocktave::{InterpreterResults, MocktaveError};
+/// let text = "# name: a\n# type: scalar\nseven\n".to_string();
+/// let results: Result<InterpreterResults, MocktaveError> = text.try_into();
+/// assert!(matches!(results, Err(MocktaveError::Parse { .. })));
+/// ```
+impl TryFrom<String> for InterpreterResults {
+    type Error = MocktaveError;
+
+    fn try_from(output: String) -> Result<Self, MocktaveError> {
+        Ok(InterpreterResults {
+            variables: reader::workspace(&output)?,
+            raw: output,
+            ..Default::default()
+        })
     }
 }
 
@@ -228,7 +432,7 @@ impl Index<&str> for InterpreterResults {
     type Output = OctaveType;
 
     fn index(&self, index: &str) -> &Self::Output {
-        &self.variables.get(index).unwrap()
+        self.variables.get(index).unwrap()
     }
 }
 
@@ -237,170 +441,3 @@ impl IndexMut<&str> for InterpreterResults {
         self.variables.get_mut(index).unwrap()
     }
 }
-
-fn parse_scalar_capture(capture: Captures) -> (String, f64) {
-    (
-        capture
-            .name("name")
-            .expect("Name not found")
-            .as_str()
-            .to_string(),
-        f64::from_str(
-            &capture
-                .name("data")
-                .expect("No value for scalar data.")
-                .as_str()
-                .replace('\n', ""),
-        )
-        .expect("Could not parse f64 from string."),
-    )
-}
-
-fn parse_complex_scalar_capture(capture: Captures) -> (String, f64, f64) {
-    let string_we_want = &capture
-        .name("data")
-        .expect("No value for scalar data.")
-        .as_str()
-        .replace('\n', "")
-        .replace("(", "")
-        .replace(")", "");
-    let imre = string_we_want.split(",").into_iter().collect::<Vec<&str>>();
-
-    (
-        capture
-            .name("name")
-            .expect("Name not found")
-            .as_str()
-            .to_string(),
-        f64::from_str(imre[0]).expect("Could not parse f64 from string."),
-        f64::from_str(imre[1]).expect("Could not parse f64 from string."),
-    )
-}
-
-fn parse_string_capture(capture: Captures) -> (String, String) {
-    let name = capture
-        .name("name")
-        .expect("Name not found")
-        .as_str()
-        .to_string();
-    (
-        name.clone(),
-        capture
-            .name("data")
-            .expect(&format!("No value named {name} for string data."))
-            .as_str()
-            .to_string(),
-    )
-}
-
-fn parse_cell_array_capture(
-    array_captures: regex::CaptureMatches,
-    mut element_captures: regex::CaptureMatches,
-) -> HashMap<String, OctaveType> {
-    let mut output: HashMap<String, OctaveType> = HashMap::new();
-
-    for cell_array in array_captures {
-        let name = cell_array
-            .name("name")
-            .expect("Name not found")
-            .as_str()
-            .to_string();
-        let rows = cell_array
-            .name("rows")
-            .expect("Name not found")
-            .as_str()
-            .to_string()
-            .parse()
-            .unwrap();
-        let columns = cell_array
-            .name("columns")
-            .expect("Name not found")
-            .as_str()
-            .to_string()
-            .parse()
-            .unwrap();
-
-        let mut value = vec![vec![OctaveType::Empty; columns]; rows];
-
-        for idx in 0..rows {
-            for jdx in 0..columns {
-                let cell_element = element_captures.next().unwrap();
-                let cell_element_name = cell_element
-                    .name("type")
-                    .expect("Name not found")
-                    .as_str()
-                    .to_string();
-                value[idx][jdx] = match cell_element_name.as_str() {
-                    "scalar" => OctaveType::Scalar(parse_scalar_capture(cell_element).1),
-                    "matrix" => OctaveType::Matrix(parse_matrix_capture(cell_element).1),
-                    "sq_string" | "string" => OctaveType::String(
-                        parse_string_capture(cell_element).1.replacen("\n", "", 1),
-                    ),
-                    _ => OctaveType::Empty,
-                }
-            }
-        }
-
-        output.insert(name, OctaveType::CellArray(value));
-    }
-
-    output
-}
-
-fn parse_matrix_capture(capture: Captures) -> (String, Vec<Vec<f64>>) {
-    let name = capture
-        .name("name")
-        .expect("Name not found")
-        .as_str()
-        .to_string();
-    let rows = usize::from_str(capture.name("rows").expect("No key named rows.").as_str())
-        .expect("Could not parse usize from string.");
-    let columns = usize::from_str(
-        capture
-            .name("columns")
-            .expect("No key named columns.")
-            .as_str(),
-    )
-    .expect("Could not parse usize from string.");
-
-    let mut matrix = vec![vec![0.0_f64; columns]; rows];
-    matrix = match capture.name("data") {
-        None => matrix,
-        Some(s) => {
-            if capture.get(2).unwrap().as_str().contains("diagonal") {
-                s.as_str()
-                    .replacen('\n', " ", rows - 1)
-                    .replace('\n', "")
-                    .split(' ')
-                    .map(|elem| match f64::from_str(elem) {
-                        Ok(val) => val,
-                        Err(_) => f64::NAN,
-                    })
-                    .enumerate()
-                    .map(|(idx, element)| matrix[idx][idx] = element)
-                    .for_each(drop);
-            } else {
-                let data = s
-                    .as_str()
-                    .replacen(' ', "", 1)
-                    .replace('\n', "")
-                    .split(' ')
-                    .map(|elem| match f64::from_str(elem) {
-                        Ok(val) => val,
-                        Err(_) => f64::NAN,
-                    })
-                    .collect::<Vec<f64>>();
-                let mut counter: usize = 0;
-                for i in 0..rows {
-                    for j in 0..columns {
-                        matrix[i][j] = data[counter];
-                        counter += 1;
-                    }
-                }
-            }
-            matrix
-        }
-    };
-
-    (name, matrix)
-}
@@ -2,13 +2,91 @@
 #![warn(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+mod async_interpreter;
+pub use async_interpreter::AsyncInterpreter;
+mod backend;
+pub use backend::Backend;
+mod builder;
+pub use builder::InterpreterBuilder;
+mod call;
+mod cassette;
+pub use cassette::{Cassette, RecordMode};
 pub mod cookbook;
+#[cfg(feature = "docker")]
+mod docker;
+#[cfg(feature = "docker")]
+pub use docker::{DockerBackend, PullPolicy};
+mod error;
+pub use error::{MocktaveError, OctaveError, OctaveWarning, StackFrame};
+mod fake;
+pub use fake::FakeBackend;
 mod interpreter_results;
 pub use interpreter_results::InterpreterResults;
+mod literal;
+mod local_process;
+pub use local_process::LocalProcess;
+mod nd_array;
+pub use nd_array::NdArray;
 mod octave_types;
-pub use octave_types::OctaveType;
+pub use octave_types::{IntegerClass, OctaveType, SparseParts, StructFields, Triplets};
+mod options;
+mod outputs;
+pub use outputs::Outputs;
+mod pool;
+pub use pool::{InterpreterPool, PooledInterpreter};
+mod reader;
+mod session;
+mod shared;
+mod writer;
+
+/// Generate strongly typed Rust functions that call Octave functions. Each signature becomes a
+/// function of the same name, whose arguments are converted with `Into<OctaveType>` and whose
+/// result is converted with `From<OctaveType>`.
+/// ```
+/// mocktave::octave_fn! {
+///     fn primes(n: i32) -> Vec<i32>;
+///     fn norm(x: Vec<Vec<f64>>, p: f64) -> f64;
+/// }
+/// assert_eq!(primes(10), vec![2, 3, 5, 7]);
+/// assert_eq!(norm(vec![vec![3.0, 4.0]], 2.0), 5.0);
+/// ```
+/// A tuple return type asks for that many outputs, and no return type asks for none
+/// ```
+/// mocktave::octave_fn! {
+///     /// Eigenvectors and eigenvalues
+///     pub fn eig(a: Vec<Vec<f64>>) -> (Vec<Vec<f64>>, Vec<Vec<f64>>);
+///     fn disp(x: f64);
+/// }
+/// let (vectors, values) = eig(vec![vec![2.0, 0.0], vec![0.0, 3.0]]);
+/// assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+/// assert_eq!(values, vec![vec![2.0, 0.0], vec![0.0, 3.0]]);
+/// disp(1.0);
+/// ```
+#[cfg(feature = "macros")]
+pub use mocktave_macros::octave_fn;
+
+#[doc(hidden)]
+pub mod __private {
+    use crate::{shared, OctaveType, Outputs};
+
+    /// Used by the code that `octave_fn!` generates
+    pub fn call<Z: Outputs>(function: &str, args: Vec<OctaveType>) -> Z {
+        shared::lease()
+            .expect("Could not start the interpreter.")
+            .call_n(function, &args)
+    }
+}
 
 /// Evaluate a few lines of Octave code and extract the results.
+///
+/// This and the other free functions borrow an interpreter that is shared by the whole process,
+/// so only the first call pays for starting Octave. Interpreters are started as they are needed,
+/// up to one per thread the machine can run at once, and each call starts with a clear workspace.
+/// Setting the environment variable `MOCKTAVE_ISOLATE` to anything but `0` gives every call a
+/// new Octave session instead, for scripts that leave behind more than variables, like globals
+/// or `warning` settings. The shared containers are labelled `mocktave.shared` and removed with
+/// the `docker` command line client when the process exits. Without the client, or when the
+/// process is killed, they keep running until [`shutdown`] is called or they are removed by hand.
 /// ```
 /// let res = mocktave::eval("a = 5+2");
 /// assert_eq!(res.get_scalar("a").unwrap(), 7_f64);
@@ -22,7 +100,38 @@ pub use octave_types::OctaveType;
 /// assert_eq!(res.get_string("a").unwrap(), "asdf");
 /// ```
 pub fn eval(input: &str) -> InterpreterResults {
-    Interpreter::default().eval(input)
+    shared::lease()
+        .expect("Could not start the interpreter.")
+        .eval(input)
+}
+
+/// Evaluate a few lines of Octave code and extract the results, reporting failures instead of
+/// panicking.
+/// ```
+/// let res = mocktave::try_eval("a = 5+2").unwrap();
+/// assert_eq!(res.get_scalar("a").unwrap(), 7_f64);
+/// ```
+/// Errors raised by Octave are returned as [`MocktaveError::Octave`]
+/// ```
+/// let err = mocktave::try_eval("error('boom')").unwrap_err();
+/// assert!(matches!(err, mocktave::MocktaveError::Octave { .. }));
+/// ```
+pub fn try_eval(input: &str) -> Result<InterpreterResults, MocktaveError> {
+    shared::lease()?.try_eval(input)
+}
+
+/// Stop the interpreters shared by [`eval`] and the other free functions right away, instead of
+/// when the process exits. This also works without the `docker` command line client, which the
+/// cleanup at exit relies on. Interpreters that are in use at the time are kept, and calls made
+/// afterwards start new interpreters.
+/// ```
+/// mocktave::eval("a = 1");
+/// mocktave::shutdown();
+/// assert_eq!(mocktave::eval("a = 2").get_scalar("a").unwrap(), 2_f64);
+/// mocktave::shutdown();
+/// ```
+pub fn shutdown() {
+    shared::shutdown();
 }
 
 /// This function provides the ability to wrap Octave functions for convenient later use.
@@ -50,32 +159,61 @@ pub fn eval(input: &str) -> InterpreterResults {
 /// let should_be_zero: f64 = norm(x);
 /// assert_eq!(should_be_zero, 0.0_f64);
 /// ```
+/// Arguments are shipped to Octave as data rather than as code, so strings, cells and matrices
+/// arrive exactly as they were on the Rust side
+/// ```
+/// let upper = mocktave::wrap("upper".into());
+/// let shouted: String = upper(["abc"]);
+/// assert_eq!(shouted, "ABC");
+/// ```
 pub fn wrap<Y, Z>(function: String) -> Box<dyn Fn(Y) -> Z>
 where
     Y: IntoIterator,
-    <Y as IntoIterator>::Item: ToString,
+    <Y as IntoIterator>::Item: Into<OctaveType>,
     Z: From<OctaveType>,
 {
     Box::new(move |inputs| {
-        let mut args = vec![String::new(); 0];
-        for input in inputs.into_iter() {
-            args.push(input.to_string());
-        }
-        Z::from(
-            eval(
-                &("result_of_function = ".to_owned()
-                    + function.as_str()
-                    + "("
-                    + &args.join(", ")
-                    + ")"),
-            )
-            .get_unchecked("result_of_function"),
-        )
+        let args = inputs.into_iter().map(Into::into).collect::<Vec<_>>();
+        shared::lease()
+            .expect("Could not start the interpreter.")
+            .call(&function, &args)
+    })
+}
+
+/// Wrap an Octave function that returns more than one value. The outputs come back as a tuple,
+/// and the number of elements in that tuple is the number of outputs requested from the function,
+/// so `[q, r] = qr(A)` becomes
+/// ```
+/// let qr = mocktave::wrap_n("qr".into());
+/// let (q, r): (Vec<Vec<f64>>, Vec<Vec<f64>>) = qr([vec![vec![2.0, 0.0], vec![0.0, 3.0]]]);
+/// assert_eq!(q.len(), 2);
+/// assert_eq!(r.len(), 2);
+/// ```
+/// Functions that behave differently depending on `nargout` do so here too
+/// ```
+/// let max = mocktave::wrap_n("max".into());
+/// let (value, index): (f64, f64) = max([vec![3.0, 7.0, 5.0]]);
+/// assert_eq!(value, 7.0);
+/// assert_eq!(index, 2.0);
+/// ```
+pub fn wrap_n<Y, Z>(function: String) -> Box<dyn Fn(Y) -> Z>
+where
+    Y: IntoIterator,
+    <Y as IntoIterator>::Item: Into<OctaveType>,
+    Z: Outputs,
+{
+    Box::new(move |inputs| {
+        let args = inputs.into_iter().map(Into::into).collect::<Vec<_>>();
+        shared::lease()
+            .expect("Could not start the interpreter.")
+            .call_n(&function, &args)
     })
 }
 
 /// Create a persistent interpreter that can call a single container multiple times, resulting in
-/// more efficiency code execution.
+/// more efficiency code execution. The interpreter keeps a single Octave session alive, so the
+/// workspace carries over from one call to `eval` to the next. Its methods block on a runtime
+/// shared by every interpreter, so from async code use [`AsyncInterpreter`] instead.
 /// ```
 /// let mut interp = mocktave::Interpreter::default();
 /// let res1 = interp.eval("a = 5+2");
@@ -85,168 +223,283 @@ where
 /// let res3 = interp.eval("a = 'asdf'");
 /// assert_eq!(res3.get_string("a").unwrap(), "asdf");
 /// ```
-#[cfg(all(
-    feature = "docker",
-    not(feature = "brew-local"),
-    not(feature = "brew-src")
-))]
+/// Variables defined in one call are still around for the next one
+/// ```
+/// let mut interp = mocktave::Interpreter::default();
+/// interp.eval("a = 1");
+/// let res = interp.eval("b = a + 1");
+/// assert_eq!(res.get_scalar("a").unwrap(), 1_f64);
+/// assert_eq!(res.get_scalar("b").unwrap(), 2_f64);
+/// ```
 pub struct Interpreter {
-    docker: bollard::Docker,
-    id: String,
+    inner: AsyncInterpreter,
 }
-#[cfg(any(feature = "brew-src", feature = "brew-local"))]
-pub struct Interpreter {}
 
 impl Default for Interpreter {
     fn default() -> Self {
-        #[cfg(all(
-            feature = "docker",
-            not(feature = "brew-local"),
-            not(feature = "brew-src")
-        ))]
-        {
-            use futures_util::{stream::StreamExt, TryStreamExt};
-            return tokio::runtime::Runtime::new()
-                .expect("Cannot create tokio runtime")
-                .block_on(async {
-                    let docker = bollard::Docker::connect_with_local_defaults()
-                        .expect("Could not connect with local defaults");
-                    docker
-                        .create_image(
-                            Some(bollard::image::CreateImageOptions {
-                                from_image: "gnuoctave/octave",
-                                tag: "8.1.0",
-                                ..Default::default()
-                            }),
-                            None,
-                            None,
-                        )
-                        .try_collect::<Vec<_>>()
-                        .await
-                        .expect("Could not create image.");
-
-                    let alpine_config = bollard::container::Config {
-                        image: Some("gnuoctave/octave:8.1.0"),
-                        tty: Some(true),
-                        ..Default::default()
-                    };
-
-                    let id = docker
-                        .create_container::<&str, &str>(None, alpine_config)
-                        .await
-                        .expect("Could not create container.")
-                        .id;
-
-                    docker
-                        .start_container::<String>(&id, None)
-                        .await
-                        .expect("Could not start container");
-
-                    Interpreter { docker, id }
-                });
-        }
-
-        #[cfg(all(feature = "brew-local", not(feature = "brew-src")))]
-        return Interpreter {};
-
-        #[cfg(feature = "brew-src")]
-        return Interpreter {};
+        Interpreter::try_new().expect("Could not start the interpreter.")
     }
 }
 
 impl Interpreter {
-    /// This function does the heavy lifting in the interpreter struct.
-    pub fn eval(&self, input: &str) -> InterpreterResults {
-        #[cfg(all(
-            feature = "docker",
-            not(feature = "brew-local"),
-            not(feature = "brew-src")
-        ))]
-        {
-            use futures_util::{stream::StreamExt, TryStreamExt};
-            return tokio::runtime::Runtime::new()
-                .expect("Cannot create tokio runtime")
-                .block_on(async {
-                    // non interactive
-                    let exec = self
-                        .docker
-                        .create_exec(
-                            &self.id.clone(),
-                            bollard::exec::CreateExecOptions {
-                                attach_stdout: Some(true),
-                                attach_stderr: Some(true),
-                                cmd: Some(vec![
-                                    "octave",
-                                    "--eval",
-                                    &(input.to_string() + "\n\nsave(\"-\", \"*\");"),
-                                ]),
-                                ..Default::default()
-                            },
-                        )
-                        .await
-                        .expect("Could not create command to execute.")
-                        .id;
-
-                    let mut output_text = vec!["".to_string(); 0];
-
-                    if let bollard::exec::StartExecResults::Attached { mut output, .. } = self
-                        .docker
-                        .start_exec(&exec, None)
-                        .await
-                        .expect("Execution of command failed.")
-                    {
-                        while let Some(Ok(msg)) = output.next().await {
-                            output_text.push(msg.to_string());
-                            print!("{}", msg);
-                        }
-                    } else {
-                        unreachable!();
-                    }
-
-                    return InterpreterResults::from(output_text.join(""));
-                });
+    /// Start a new interpreter, reporting failures instead of panicking.
+    /// ```
+    /// let mut interp = mocktave::Interpreter::try_new().unwrap();
+    /// let res = interp.try_eval("a = 5+2").unwrap();
+    /// assert_eq!(res.get_scalar("a").unwrap(), 7_f64);
+    /// ```
+    pub fn try_new() -> Result<Self, MocktaveError> {
+        InterpreterBuilder::default().try_build()
+    }
+
+    /// Run scripts with `backend` instead of one of the backends the builder starts, for example a
+    /// [`FakeBackend`] in unit tests of code that calls Octave.
+    /// ```
+    /// use mocktave::{FakeBackend, Interpreter, InterpreterResults};
+    /// let fake = FakeBackend::new()
+    ///     .on_script("v = version()", InterpreterResults::default().with_variable("v", "8.1.0"));
+    /// let mut interp = Interpreter::with_backend(fake);
+    /// assert_eq!(interp.eval("v = version()").get_string("v").unwrap(), "8.1.0");
+    /// ```
+    pub fn with_backend(backend: impl Backend + 'static) -> Self {
+        Interpreter {
+            inner: AsyncInterpreter::with_backend(backend),
         }
+    }
 
-        #[cfg(all(feature = "brew-local", not(feature = "brew-src")))]
-        {
-            let output = std::process::Command::new("octave")
-                .arg("--eval")
-                .arg(&(input.to_string() + "\n\nsave(\"-\", \"*\");"))
-                .output()
-                .expect("");
+    /// Configure where and how the interpreter runs Octave before starting it, for example to use
+    /// a different version of Octave, or one installed on this machine instead of Docker.
+    /// ```
+    /// let mut interp = mocktave::Interpreter::builder()
+    ///     .image("gnuoctave/octave")
+    ///     .tag("8.1.0")
+    ///     .build();
+    /// let res = interp.eval("v = OCTAVE_VERSION");
+    /// assert_eq!(res.get_string("v").unwrap(), "8.1.0");
+    /// ```
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
 
-            return InterpreterResults::from(String::from_utf8(output.stdout).unwrap());
-        }
+    /// Choose whether whatever scripts print is also echoed to this process' stdout and stderr.
+    /// Nothing is echoed by default, but the output is always available through
+    /// [`InterpreterResults::stdout`] and [`InterpreterResults::stderr`].
+    /// ```
+    /// let mut interp = mocktave::Interpreter::default();
+    /// interp.set_echo(true);
+    /// let res = interp.eval("disp('shown in the terminal too')");
+    /// assert_eq!(res.stdout(), "shown in the terminal too\n");
+    /// ```
+    pub fn set_echo(&mut self, echo: bool) {
+        self.options().echo = echo;
+    }
 
-        #[cfg(feature = "brew-src")]
-        {
-            let output = std::process::Command::new("octave")
-                .arg("--eval")
-                .arg(&(input.to_string() + "\n\nsave(\"-\", \"*\");"))
-                .output()
-                .expect("");
+    /// Raise warnings with the identifier `identifier` as errors from now on, so that numerically
+    /// suspicious results fail loudly instead of passing with a note on stderr.
+    /// ```
+    /// let mut interp = mocktave::Interpreter::default();
+    /// interp.promote_warning("Octave:singular-matrix");
+    /// let err = interp.try_eval("x = inv([1 2; 2 4]);").unwrap_err();
+    /// assert!(matches!(err, mocktave::MocktaveError::Octave { .. }));
+    /// ```
+    pub fn promote_warning(&mut self, identifier: &str) {
+        self.options().promote(identifier);
+    }
 
-            return InterpreterResults::from(String::from_utf8(output.stdout).unwrap());
+    /// Give up on scripts that take longer than `timeout` to run, or never give up if it is
+    /// `None`, which is the default. A script that runs out of time is stopped by restarting
+    /// Octave, so the interpreter can be used again straight away but its workspace is cleared.
+    /// ```
+    /// use std::time::Duration;
+    /// let mut interp = mocktave::Interpreter::default();
+    /// interp.set_timeout(Some(Duration::from_secs(2)));
+    /// let err = interp.try_eval("while true\nend").unwrap_err();
+    /// assert_eq!(err, mocktave::MocktaveError::Timeout(Duration::from_secs(2)));
+    /// let res = interp.eval("a = 1");
+    /// assert_eq!(res.get_scalar("a").unwrap(), 1_f64);
+    /// ```
+    pub fn set_timeout(&mut self, timeout: Option<std::time::Duration>) {
+        self.options().timeout = timeout;
+    }
+
+    /// This function does the heavy lifting in the interpreter struct. The script runs in the
+    /// interpreter's persistent session, so it can use anything defined by earlier calls.
+    /// If the script raises an error, the workspace up to that point is returned and the error
+    /// is available through [`InterpreterResults::error`].
+    /// ```
+    /// let mut interp = mocktave::Interpreter::default();
+    /// let res = interp.eval("a = 1;\nerror('boom');\nb = 2;");
+    /// assert_eq!(res.get_scalar("a").unwrap(), 1_f64);
+    /// assert_eq!(res.get_scalar("b"), None);
+    /// assert_eq!(res.error().unwrap().message, "boom");
+    /// ```
+    pub fn eval(&mut self, input: &str) -> InterpreterResults {
+        self.run(input).expect("Could not evaluate Octave code.")
+    }
+
+    /// Run a script like [`Interpreter::eval`], but report failures instead of panicking. Errors
+    /// raised by Octave while running the script are returned as [`MocktaveError::Octave`].
+    /// ```
+    /// let mut interp = mocktave::Interpreter::try_new().unwrap();
+    /// let err = interp.try_eval("x = undefined_function_for_sure(1)").unwrap_err();
+    /// assert!(matches!(err, mocktave::MocktaveError::Octave { .. }));
+    /// ```
+    pub fn try_eval(&mut self, input: &str) -> Result<InterpreterResults, MocktaveError> {
+        let timeout = self.options().timeout;
+        self.try_eval_with_timeout(input, timeout)
+    }
+
+    /// Run a script like [`Interpreter::eval`], using `timeout` instead of the limit set with
+    /// [`Interpreter::set_timeout`].
+    /// ```
+    /// use std::time::Duration;
+    /// let mut interp = mocktave::Interpreter::default();
+    /// let res = interp.eval_with_timeout("pause(0.1); a = 1", Some(Duration::from_secs(10)));
+    /// assert_eq!(res.get_scalar("a").unwrap(), 1_f64);
+    /// ```
+    pub fn eval_with_timeout(
+        &mut self,
+        input: &str,
+        timeout: Option<std::time::Duration>,
+    ) -> InterpreterResults {
+        self.run_with_timeout(input, timeout)
+            .expect("Could not evaluate Octave code.")
+    }
+
+    /// Run a script like [`Interpreter::try_eval`], using `timeout` instead of the limit set with
+    /// [`Interpreter::set_timeout`].
+    /// ```
+    /// use std::time::Duration;
+    /// let mut interp = mocktave::Interpreter::try_new().unwrap();
+    /// let err = interp
+    ///     .try_eval_with_timeout("pause(60)", Some(Duration::from_millis(500)))
+    ///     .unwrap_err();
+    /// assert_eq!(err, mocktave::MocktaveError::Timeout(Duration::from_millis(500)));
+    /// ```
+    pub fn try_eval_with_timeout(
+        &mut self,
+        input: &str,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<InterpreterResults, MocktaveError> {
+        let results = self.run_with_timeout(input, timeout)?;
+        match results.error() {
+            Some(error) => Err(error.clone().into()),
+            None => Ok(results),
         }
     }
-}
 
-#[cfg(all(
-    feature = "docker",
-    not(feature = "brew-local"),
-    not(feature = "brew-src")
-))]
-impl Drop for Interpreter {
-    fn drop(&mut self) {
-        tokio::runtime::Runtime::new()
-            .expect("Cannot create tokio runtime to to remove container")
-            .block_on(self.docker.remove_container(
-                &self.id.clone(),
-                Some(bollard::container::RemoveContainerOptions {
-                    force: true,
-                    ..Default::default()
-                }),
-            ))
-            .expect("Could not remove container.");
+    /// Create the variable `name` in the session and give it `value`. The value is sent as data in
+    /// Octave's text save format and `load`ed, rather than written into a script, so large
+    /// matrices don't need to fit on a command line and floats keep every bit of precision.
+    /// ```
+    /// let mut interp = mocktave::Interpreter::default();
+    /// interp.set("K", vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+    /// let res = interp.eval("k = K(2, 1)");
+    /// assert_eq!(res.get_scalar("k").unwrap(), 0.3);
+    /// ```
+    pub fn set(&mut self, name: &str, value: impl Into<OctaveType>) {
+        self.try_set(name, value)
+            .expect("Could not set the variable.")
+    }
+
+    /// Create a variable like [`Interpreter::set`], but report failures instead of panicking.
+    /// ```
+    /// let mut interp = mocktave::Interpreter::try_new().unwrap();
+    /// interp.try_set("s", "it's \"quoted\"").unwrap();
+    /// let res = interp.try_eval("n = numel(s)").unwrap();
+    /// assert_eq!(res.get_scalar("n").unwrap(), 13.0);
+    /// ```
+    pub fn try_set(
+        &mut self,
+        name: &str,
+        value: impl Into<OctaveType>,
+    ) -> Result<(), MocktaveError> {
+        self.load(&[(name.to_string(), value.into())])
+    }
+
+    /// Call the Octave function `function` with `args` and return its first output. The
+    /// arguments are sent the same way as [`Interpreter::set`].
+    /// ```
+    /// use mocktave::OctaveType;
+    /// let mut interp = mocktave::Interpreter::default();
+    /// let norm: f64 = interp.call("norm", &[vec![vec![3.0, 4.0]].into()]);
+    /// assert_eq!(norm, 5.0);
+    /// let upper: String = interp.call("upper", &[OctaveType::from("abc")]);
+    /// assert_eq!(upper, "ABC");
+    /// let pi: f64 = interp.call("pi", &[]);
+    /// assert_eq!(pi, std::f64::consts::PI);
+    /// ```
+    pub fn call<Z: From<OctaveType>>(&mut self, function: &str, args: &[OctaveType]) -> Z {
+        self.try_call(function, args)
+            .expect("Could not call the function.")
+    }
+
+    /// Call a function like [`Interpreter::call`], but report failures instead of panicking.
+    /// ```
+    /// let mut interp = mocktave::Interpreter::try_new().unwrap();
+    /// let err = interp.try_call::<f64>("error", &["boom".into()]).unwrap_err();
+    /// assert!(matches!(err, mocktave::MocktaveError::Octave { .. }));
+    /// ```
+    pub fn try_call<Z: From<OctaveType>>(
+        &mut self,
+        function: &str,
+        args: &[OctaveType],
+    ) -> Result<Z, MocktaveError> {
+        let (output,) = self.try_call_n(function, args)?;
+        Ok(output)
+    }
+
+    /// Call a function that returns more than one value, like [`wrap_n`] does. The number of
+    /// elements in the tuple is the number of outputs requested from the function.
+    /// ```
+    /// let mut interp = mocktave::Interpreter::default();
+    /// let (value, index): (f64, f64) = interp.call_n("max", &[vec![3.0, 7.0, 5.0].into()]);
+    /// assert_eq!((value, index), (7.0, 2.0));
+    /// ```
+    pub fn call_n<Z: Outputs>(&mut self, function: &str, args: &[OctaveType]) -> Z {
+        self.try_call_n(function, args)
+            .expect("Could not call the function.")
+    }
+
+    /// Call a function like [`Interpreter::call_n`], but report failures instead of panicking.
+    pub fn try_call_n<Z: Outputs>(
+        &mut self,
+        function: &str,
+        args: &[OctaveType],
+    ) -> Result<Z, MocktaveError> {
+        let call = call::Call::new(function, args, Z::COUNT);
+        self.load(&call.inputs)?;
+        // Outputs that were assigned before a failure would stay behind, so clean up either way
+        let outputs = self
+            .try_eval(&call.script())
+            .and_then(|results| call.outputs(&results));
+        let cleared = call.cleanup().map(|cleanup| self.run(&cleanup)).transpose();
+        let outputs = outputs?;
+        cleared?;
+        Ok(outputs)
+    }
+
+    fn options(&mut self) -> &mut options::Options {
+        &mut self.inner.options
+    }
+
+    /// Send `variables` to the session as a workspace file and `load` it.
+    fn load(&mut self, variables: &[(String, OctaveType)]) -> Result<(), MocktaveError> {
+        async_interpreter::runtime()?.block_on(self.inner.load(variables))
+    }
+
+    /// Run a script, keeping any error Octave raised along the way in the results.
+    fn run(&mut self, input: &str) -> Result<InterpreterResults, MocktaveError> {
+        let timeout = self.options().timeout;
+        self.run_with_timeout(input, timeout)
+    }
+
+    /// Run a script like [`Interpreter::run`], giving up after `timeout`.
+    fn run_with_timeout(
+        &mut self,
+        input: &str,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<InterpreterResults, MocktaveError> {
+        async_interpreter::runtime()?.block_on(self.inner.run_with_timeout(input, timeout))
     }
 }
@@ -0,0 +1,277 @@
+//! Turning an [`OctaveType`] back into Octave code that evaluates to the same value.
+
+use crate::writer;
+use crate::{IntegerClass, NdArray, OctaveType, Triplets};
+
+impl OctaveType {
+    /// Write the value as Octave code that evaluates to exactly the same value, class and shape,
+    /// for writing values into scripts by hand. This is also how values are displayed. Passing
+    /// values to an interpreter doesn't need it, since [`Interpreter::set`] and
+    /// [`Interpreter::call`] send them as data rather than as code.
+    /// ```
+    /// use mocktave::{IntegerClass, OctaveType};
+    /// assert_eq!(OctaveType::Scalar(0.1).to_octave_literal(), "0.1");
+    /// assert_eq!(OctaveType::Scalar(f64::NEG_INFINITY).to_octave_literal(), "-Inf");
+    /// assert_eq!(
+    ///     OctaveType::Matrix(vec![vec![1.0, 2.0], vec![3.0, 4.0]]).to_octave_literal(),
+    ///     "[1.0, 2.0; 3.0, 4.0]"
+    /// );
+    /// assert_eq!(OctaveType::String("it's".to_string()).to_octave_literal(), "\"it's\"");
+    /// assert_eq!(OctaveType::Integer(IntegerClass::Int32, 5).to_octave_literal(), "int32(5)");
+    /// assert_eq!(
+    ///     OctaveType::CellArray(vec![vec![
+    ///         OctaveType::String("a".to_string()),
+    ///         OctaveType::CellArray(vec![vec![OctaveType::Scalar(1.0)]]),
+    ///     ]]).to_octave_literal(),
+    ///     "{\"a\", {1.0}}"
+    /// );
+    /// ```
+    ///
+    /// [`Interpreter::set`]: crate::Interpreter::set
+    /// [`Interpreter::call`]: crate::Interpreter::call
+    pub fn to_octave_literal(&self) -> String {
+        match self {
+            OctaveType::Scalar(scalar) => float(*scalar),
+            OctaveType::ComplexScalar(re, im) => format!("complex({}, {})", float(*re), float(*im)),
+            OctaveType::Matrix(matrix) => rows(matrix, "zeros", |el| float(*el)),
+            OctaveType::ComplexMatrix(matrix) => format!(
+                "complex({}, {})",
+                rows(matrix, "zeros", |(re, _)| float(*re)),
+                rows(matrix, "zeros", |(_, im)| float(*im))
+            ),
+            OctaveType::Integer(class, integer) => {
+                format!("{class}({})", integer_element(*class, *integer))
+            }
+            OctaveType::IntegerMatrix(class, matrix) => format!(
+                "{class}({})",
+                rows(matrix, "zeros", |el| integer_element(*class, *el))
+            ),
+            OctaveType::NdArray(array) => reshape(array, |el| float(*el)),
+            OctaveType::ComplexNdArray(array) => format!(
+                "complex({}, {})",
+                reshape(array, |(re, _)| float(*re)),
+                reshape(array, |(_, im)| float(*im))
+            ),
+            OctaveType::BoolNdArray(array) => {
+                format!(
+                    "logical({})",
+                    reshape(array, |el| u8::from(*el).to_string())
+                )
+            }
+            OctaveType::IntegerNdArray(class, array) => format!(
+                "{class}({})",
+                reshape(array, |el| integer_element(*class, *el))
+            ),
+            OctaveType::SparseMatrix {
+                rows,
+                cols,
+                triplets,
+            } => sparse(*rows, *cols, triplets, |values| {
+                row(values, |el| float(*el))
+            }),
+            OctaveType::SparseComplexMatrix {
+                rows,
+                cols,
+                triplets,
+            } => sparse(*rows, *cols, triplets, |values| {
+                format!(
+                    "complex({}, {})",
+                    row(values, |(re, _)| float(*re)),
+                    row(values, |(_, im)| float(*im))
+                )
+            }),
+            OctaveType::SparseBoolMatrix {
+                rows,
+                cols,
+                triplets,
+            } => {
+                if triplets.is_empty() {
+                    format!("logical(sparse({rows}, {cols}))")
+                } else {
+                    sparse(*rows, *cols, triplets, |values| {
+                        format!("logical({})", row(values, |el| u8::from(*el).to_string()))
+                    })
+                }
+            }
+            OctaveType::String(string) => string_literal(string),
+            OctaveType::Bool(boolean) => boolean.to_string(),
+            OctaveType::BoolMatrix(matrix) => {
+                if matrix.first().map(Vec::is_empty).unwrap_or(true) {
+                    rows(matrix, "false", |_| String::new())
+                } else {
+                    format!(
+                        "logical({})",
+                        rows(matrix, "zeros", |el| u8::from(*el).to_string())
+                    )
+                }
+            }
+            OctaveType::CellArray(cell) => {
+                if cell.first().map(Vec::is_empty).unwrap_or(true) {
+                    format!("cell({}, {})", cell.len(), 0)
+                } else {
+                    let rows = cell
+                        .iter()
+                        .map(|row| {
+                            row.iter()
+                                .map(OctaveType::to_octave_literal)
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        })
+                        .collect::<Vec<String>>();
+                    format!("{{{}}}", rows.join("; "))
+                }
+            }
+            OctaveType::Struct(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, value)| {
+                        format!(
+                            "{}, {{{}}}",
+                            string_literal(name),
+                            value.to_octave_literal()
+                        )
+                    })
+                    .collect::<Vec<String>>();
+                format!("struct({})", fields.join(", "))
+            }
+            OctaveType::StructArray(array) => {
+                // Octave has no struct array whose elements have different fields, so the
+                // closest code is code that fails
+                let fields = match writer::columns_of(array) {
+                    Ok(fields) => fields,
+                    Err(error) => return format!("error({})", string_literal(&error.to_string())),
+                };
+                if fields.is_empty() {
+                    let columns = array.first().map(Vec::len).unwrap_or(0);
+                    return format!("repmat(struct(), {}, {columns})", array.len());
+                }
+                let fields = fields
+                    .iter()
+                    .map(|(name, cell)| {
+                        format!("{}, {}", string_literal(name), cell.to_octave_literal())
+                    })
+                    .collect::<Vec<String>>();
+                format!("struct({})", fields.join(", "))
+            }
+            OctaveType::Empty => "[]".to_string(),
+            OctaveType::Error(message) => format!("error({})", string_literal(message)),
+        }
+    }
+}
+
+/// Write a float so that Octave reads back exactly the same number.
+pub(crate) fn float(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Inf" } else { "-Inf" }.to_string()
+    } else {
+        // `Debug` gives the shortest representation that round trips, in a syntax Octave accepts
+        format!("{value:?}")
+    }
+}
+
+/// Write an integer element. Octave reads numeric literals as doubles, so integers that a double
+/// can't hold exactly are rebuilt from their bits instead.
+fn integer_element(class: IntegerClass, value: i128) -> String {
+    const FLINTMAX: i128 = 1 << 53;
+    if (-FLINTMAX..=FLINTMAX).contains(&value) {
+        value.to_string()
+    } else {
+        let bits = value as u64;
+        format!(
+            "typecast(uint32([{}, {}]), \"{class}\")",
+            bits & 0xFFFF_FFFF,
+            bits >> 32
+        )
+    }
+}
+
+/// Write a string in double quotes, escaping anything that would end it early.
+fn string_literal(string: &str) -> String {
+    let mut literal = String::with_capacity(string.len() + 2);
+    literal.push('"');
+    for character in string.chars() {
+        match character {
+            '\\' => literal.push_str("\\\\"),
+            '"' => literal.push_str("\\\""),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            '\0' => literal.push_str("\\0"),
+            character => literal.push(character),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Write a matrix given as rows, falling back to `empty(rows, columns)` when it has no elements.
+fn rows<T>(matrix: &[Vec<T>], empty: &str, element: impl Fn(&T) -> String) -> String {
+    let columns = matrix.first().map(Vec::len).unwrap_or(0);
+    if columns == 0 {
+        return format!("{empty}({}, {columns})", matrix.len());
+    }
+    let rows = matrix
+        .iter()
+        .map(|row| row.iter().map(&element).collect::<Vec<String>>().join(", "))
+        .collect::<Vec<String>>();
+    format!("[{}]", rows.join("; "))
+}
+
+/// Write a row vector.
+fn row<T>(values: &[T], element: impl Fn(&T) -> String) -> String {
+    format!(
+        "[{}]",
+        values
+            .iter()
+            .map(element)
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// Write an array as a call to `reshape`, which is how Octave builds arrays with more than two
+/// dimensions.
+fn reshape<T>(array: &NdArray<T>, element: impl Fn(&T) -> String) -> String {
+    let shape = array
+        .shape()
+        .iter()
+        .map(|size| size.to_string())
+        .collect::<Vec<String>>();
+    format!(
+        "reshape({}, [{}])",
+        row(array.data(), element),
+        shape.join(", ")
+    )
+}
+
+/// Write a sparse matrix as a call to `sparse(i, j, v, m, n)`, so it never has to be densified.
+fn sparse<T: Clone>(
+    rows: usize,
+    cols: usize,
+    triplets: &Triplets<T>,
+    values: impl Fn(&[T]) -> String,
+) -> String {
+    if triplets.is_empty() {
+        return format!("sparse({rows}, {cols})");
+    }
+    let i = triplets
+        .iter()
+        .map(|(i, _, _)| i + 1)
+        .collect::<Vec<usize>>();
+    let j = triplets
+        .iter()
+        .map(|(_, j, _)| j + 1)
+        .collect::<Vec<usize>>();
+    let v = triplets
+        .iter()
+        .map(|(_, _, v)| v.clone())
+        .collect::<Vec<T>>();
+    format!(
+        "sparse({}, {}, {}, {rows}, {cols})",
+        row(&i, usize::to_string),
+        row(&j, usize::to_string),
+        values(&v)
+    )
+}
@@ -0,0 +1,177 @@
+//! Running Octave as a process on this machine, for when Octave is installed natively.
+
+use std::path::{Path, PathBuf};
+
+use futures_util::future::BoxFuture;
+use futures_util::{stream, Stream};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+
+use crate::backend::{self, Backend};
+use crate::session::{self, Chunk, Session, OCTAVE_ARGS};
+use crate::{InterpreterResults, MocktaveError, OctaveType};
+
+/// The programs looked for on `PATH`, in order of preference.
+const PROGRAMS: [&str; 2] = ["octave-cli", "octave"];
+
+/// Runs Octave as a child process of this one, which only needs Octave to be installed. The
+/// process is started once and lives as long as the backend does, so the workspace carries over
+/// from one script to the next just like it does in a container.
+pub struct LocalProcess {
+    program: PathBuf,
+    env: Vec<(String, String)>,
+    working_dir: Option<String>,
+    child: Child,
+    session: Session,
+}
+
+impl LocalProcess {
+    /// Start Octave from `program`, or from the first of `octave-cli` and `octave` found on
+    /// `PATH` if it is `None`.
+    pub(crate) async fn start(
+        program: Option<&Path>,
+        env: &[(String, String)],
+        working_dir: Option<&str>,
+    ) -> Result<Self, MocktaveError> {
+        let program = match program {
+            Some(program) => program.to_path_buf(),
+            None => find_program()?,
+        };
+        let (child, session) = spawn(&program, env, working_dir).await?;
+        Ok(LocalProcess {
+            program,
+            env: env.to_vec(),
+            working_dir: working_dir.map(str::to_string),
+            child,
+            session,
+        })
+    }
+}
+
+impl Backend for LocalProcess {
+    fn eval<'a>(
+        &'a mut self,
+        script: &'a str,
+        promoted: &'a [String],
+    ) -> BoxFuture<'a, Result<InterpreterResults, MocktaveError>> {
+        Box::pin(async move { self.session.eval(script, promoted).await?.into_results() })
+    }
+
+    fn load<'a>(
+        &'a mut self,
+        variables: &'a [(String, OctaveType)],
+    ) -> BoxFuture<'a, Result<(), MocktaveError>> {
+        Box::pin(async move {
+            if variables.is_empty() {
+                return Ok(());
+            }
+            let payload = backend::payload(variables)?;
+            let path =
+                std::env::temp_dir().join(format!("__mocktave_load_{}__.txt", session::token()));
+            tokio::fs::write(&path, payload)
+                .await
+                .map_err(unavailable)?;
+            let loaded = match self
+                .eval(&backend::load_script(&path.to_string_lossy()), &[])
+                .await
+            {
+                Ok(results) => backend::check(results),
+                Err(error) => Err(error),
+            };
+            // The script deletes the file itself, unless it didn't get that far
+            if loaded.is_err() {
+                let _ = tokio::fs::remove_file(&path).await;
+            }
+            loaded
+        })
+    }
+
+    fn restart(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>> {
+        Box::pin(async move {
+            self.child.kill().await.map_err(unavailable)?;
+            (self.child, self.session) =
+                spawn(&self.program, &self.env, self.working_dir.as_deref()).await?;
+            Ok(())
+        })
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>> {
+        Box::pin(async move { self.child.kill().await.map_err(unavailable) })
+    }
+}
+
+/// Find the first of [`PROGRAMS`] on `PATH`.
+fn find_program() -> Result<PathBuf, MocktaveError> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    PROGRAMS
+        .iter()
+        .flat_map(|program| {
+            std::env::split_paths(&path)
+                .map(move |dir| dir.join(format!("{program}{}", std::env::consts::EXE_SUFFIX)))
+        })
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| {
+            MocktaveError::BackendUnavailable(
+                "Could not find `octave-cli` or `octave` on PATH.".to_string(),
+            )
+        })
+}
+
+/// Start an Octave process and a session in it. The process is killed when the returned `Child`
+/// is dropped.
+async fn spawn(
+    program: &Path,
+    env: &[(String, String)],
+    working_dir: Option<&str>,
+) -> Result<(Child, Session), MocktaveError> {
+    let mut command = Command::new(program);
+    command
+        .args(OCTAVE_ARGS)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(dir) = working_dir {
+        command.current_dir(dir);
+    }
+    let mut child = command.spawn().map_err(|error| {
+        MocktaveError::BackendUnavailable(format!(
+            "Could not start `{}`: {error}",
+            program.display()
+        ))
+    })?;
+
+    let (Some(input), Some(stdout), Some(stderr)) =
+        (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    else {
+        unreachable!();
+    };
+    let output = stream::select(chunks(stdout, Chunk::Stdout), chunks(stderr, Chunk::Stderr));
+    let session = Session::new(Box::pin(input), Box::pin(output)).await?;
+    Ok((child, session))
+}
+
+/// Read `reader` a piece at a time until it is closed.
+fn chunks<R: AsyncRead + Unpin + Send + 'static>(
+    reader: R,
+    chunk: fn(Vec<u8>) -> Chunk,
+) -> impl Stream<Item = Result<Chunk, MocktaveError>> + Send {
+    stream::unfold(Some(reader), move |reader| async move {
+        let mut reader = reader?;
+        let mut buffer = vec![0; 8192];
+        match reader.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok(chunk(buffer)), Some(reader)))
+            }
+            // Report the error once, and end the stream after that
+            Err(error) => Some((Err(unavailable(error)), None)),
+        }
+    })
+}
+
+fn unavailable(error: std::io::Error) -> MocktaveError {
+    MocktaveError::BackendUnavailable(error.to_string())
+}
@@ -0,0 +1,134 @@
+use std::ops::{Index, IndexMut};
+
+/// An array with any number of dimensions, as produced by `zeros(2, 3, 4)`. The elements are kept
+/// in column-major order, the same way Octave stores them.
+/// ```
+/// use mocktave::NdArray;
+/// let x = NdArray::new(vec![2, 2, 2], (1..=8).collect::<Vec<i32>>()).unwrap();
+/// assert_eq!(x[&[1, 0, 1][..]], 6);
+/// assert_eq!(x.get(&[0, 1, 0]), Some(&3));
+/// assert_eq!(x.get(&[2, 0, 0]), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
+pub struct NdArray<T> {
+    shape: Vec<usize>,
+    data: Vec<T>,
+}
+
+impl<T> NdArray<T> {
+    /// Make an array from its shape and its elements in column-major order, if the number of
+    /// elements matches the shape.
+    pub fn new(shape: Vec<usize>, data: Vec<T>) -> Option<Self> {
+        if shape.iter().product::<usize>() == data.len() {
+            Some(NdArray { shape, data })
+        } else {
+            None
+        }
+    }
+    /// The size of each dimension
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+    /// The elements in column-major order
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+    /// The number of elements
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+    /// Whether or not there are any elements
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+    /// Turn zero-based subscripts into the position of that element in `data`, if the subscripts
+    /// are in bounds. Missing trailing subscripts are taken to be zero.
+    /// ```
+    /// use mocktave::NdArray;
+    /// let x = NdArray::new(vec![2, 3, 4], vec![0.0; 24]).unwrap();
+    /// assert_eq!(x.linear_index(&[1, 2, 3]), Some(23));
+    /// assert_eq!(x.linear_index(&[1, 2]), Some(5));
+    /// ```
+    pub fn linear_index(&self, subscripts: &[usize]) -> Option<usize> {
+        if subscripts.len() > self.shape.len() {
+            return None;
+        }
+        let mut index = 0;
+        let mut stride = 1;
+        for (position, size) in self.shape.iter().enumerate() {
+            let subscript = subscripts.get(position).copied().unwrap_or(0);
+            if subscript >= *size {
+                return None;
+            }
+            index += subscript * stride;
+            stride *= size;
+        }
+        Some(index)
+    }
+    /// Get an element by its zero-based subscripts, if they are in bounds
+    pub fn get(&self, subscripts: &[usize]) -> Option<&T> {
+        self.linear_index(subscripts)
+            .and_then(|index| self.data.get(index))
+    }
+    /// Get a mutable reference to an element by its zero-based subscripts, if they are in bounds
+    pub fn get_mut(&mut self, subscripts: &[usize]) -> Option<&mut T> {
+        self.linear_index(subscripts)
+            .and_then(|index| self.data.get_mut(index))
+    }
+    /// Split the array into its elements in column-major order and its shape
+    /// ```
+    /// use mocktave::NdArray;
+    /// let x = NdArray::new(vec![1, 2, 2], vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+    /// let (data, shape) = x.into_parts();
+    /// assert_eq!(data, vec![1.0, 2.0, 3.0, 4.0]);
+    /// assert_eq!(shape, vec![1, 2, 2]);
+    /// ```
+    pub fn into_parts(self) -> (Vec<T>, Vec<usize>) {
+        (self.data, self.shape)
+    }
+    /// Apply `f` to every element, keeping the shape
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> NdArray<U> {
+        NdArray {
+            shape: self.shape,
+            data: self.data.into_iter().map(f).collect(),
+        }
+    }
+}
+
+/// Convert a matrix given as rows into a two dimensional array
+/// ```
+/// use mocktave::NdArray;
+/// let x: NdArray<f64> = vec![vec![1.0, 2.0], vec![3.0, 4.0]].into();
+/// assert_eq!(x.shape(), &[2, 2]);
+/// assert_eq!(x.data(), &[1.0, 3.0, 2.0, 4.0]);
+/// ```
+impl<T> From<Vec<Vec<T>>> for NdArray<T> {
+    fn from(rows: Vec<Vec<T>>) -> Self {
+        let shape = vec![rows.len(), rows.first().map(Vec::len).unwrap_or(0)];
+        let mut columns = rows
+            .into_iter()
+            .map(|row| row.into_iter())
+            .collect::<Vec<_>>();
+        let mut data = Vec::with_capacity(shape[0] * shape[1]);
+        for _ in 0..shape[1] {
+            for row in columns.iter_mut() {
+                data.extend(row.next());
+            }
+        }
+        NdArray { shape, data }
+    }
+}
+
+impl<T> Index<&[usize]> for NdArray<T> {
+    type Output = T;
+
+    fn index(&self, index: &[usize]) -> &Self::Output {
+        self.get(index).expect("Index out of bounds")
+    }
+}
+
+impl<T> IndexMut<&[usize]> for NdArray<T> {
+    fn index_mut(&mut self, index: &[usize]) -> &mut Self::Output {
+        self.get_mut(index).expect("Index out of bounds")
+    }
+}
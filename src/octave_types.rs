@@ -1,5 +1,8 @@
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use crate::{MocktaveError, NdArray};
 
 /// Possible types that can be returned from Octave through this library.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -12,18 +15,159 @@ pub enum OctaveType {
     /// A numerical matrix, accounting 2 dimensional matrices of scalars. The underlying type is
     /// `Vec<Vec<f64>>`.
     Matrix(Vec<Vec<f64>>),
+    /// A complex matrix, as produced by `fft` or `eig`. Each element is a pair of `f64`s, the
+    /// same as `ComplexScalar`. The underlying type is `Vec<Vec<(f64, f64)>>`.
+    ComplexMatrix(Vec<Vec<(f64, f64)>>),
     /// A string value, accounting for both single and double quote strings. The underlying type is
     /// `String`.
     String(String),
+    /// An integer value that keeps track of its Octave class, e.g. `int32(5)`. The underlying
+    /// type is `i128`, which holds every value of every Octave integer class exactly.
+    Integer(IntegerClass, i128),
+    /// An integer matrix that keeps track of its Octave class, e.g. `uint8([1, 2; 3, 4])`. The
+    /// underlying type is `Vec<Vec<i128>>`.
+    IntegerMatrix(IntegerClass, Vec<Vec<i128>>),
+    /// An array of `f64`s with more than two dimensions, as produced by `zeros(2, 3, 4)`, or a
+    /// matrix without rows like `zeros(0, 3)`, whose number of columns a `Matrix` can't keep. The
+    /// underlying type is `NdArray<f64>`.
+    NdArray(NdArray<f64>),
+    /// A complex array with more than two dimensions, or a complex matrix without rows. The
+    /// underlying type is `NdArray<(f64, f64)>`.
+    ComplexNdArray(NdArray<(f64, f64)>),
+    /// A logical array with more than two dimensions, or a logical matrix without rows. The
+    /// underlying type is `NdArray<bool>`.
+    BoolNdArray(NdArray<bool>),
+    /// An integer array with more than two dimensions, or an integer matrix without rows, that
+    /// keeps track of its Octave class. The underlying type is `NdArray<i128>`.
+    IntegerNdArray(IntegerClass, NdArray<i128>),
+    /// A sparse matrix, as produced by `sparse(i, j, v, m, n)`. Only the nonzero elements are
+    /// kept, as `(row, column, value)` triplets with zero-based indices.
+    SparseMatrix {
+        /// The number of rows
+        rows: usize,
+        /// The number of columns
+        cols: usize,
+        /// The nonzero elements, in column-major order
+        triplets: Triplets<f64>,
+    },
+    /// A sparse complex matrix, where each value is a pair of `f64`s like in `ComplexScalar`.
+    SparseComplexMatrix {
+        /// The number of rows
+        rows: usize,
+        /// The number of columns
+        cols: usize,
+        /// The nonzero elements, in column-major order
+        triplets: Triplets<(f64, f64)>,
+    },
+    /// A sparse logical matrix, as produced by comparing sparse matrices.
+    SparseBoolMatrix {
+        /// The number of rows
+        rows: usize,
+        /// The number of columns
+        cols: usize,
+        /// The nonzero elements, in column-major order
+        triplets: Triplets<bool>,
+    },
+    /// A logical value, as produced by `true` or a comparison. The underlying type is `bool`.
+    Bool(bool),
+    /// A logical matrix, as produced by comparing matrices. The underlying type is
+    /// `Vec<Vec<bool>>`.
+    BoolMatrix(Vec<Vec<bool>>),
     /// A cell array, which is essentially a matrix of non-numeric types.  The underlying type is
     /// `Vec<Vec<OctaveType>>`.
     CellArray(Vec<Vec<OctaveType>>),
+    /// A struct, as produced by `s.a = 1`. The underlying type is `StructFields`.
+    Struct(StructFields),
+    /// A struct array, as produced by `s(2).a = 1`, where every element has the same fields. The
+    /// underlying type is `Vec<Vec<StructFields>>`.
+    StructArray(Vec<Vec<StructFields>>),
     /// Something a value might be empty. This is mostly for the implementation of `Default`.
     Empty,
     /// Sometimes a value might be an error too.
     Error(String),
 }
 
+/// The fields of a struct, as name and value pairs in the order Octave has them in.
+pub type StructFields = Vec<(String, OctaveType)>;
+
+/// The nonzero elements of a sparse matrix, as `(row, column, value)` with zero-based indices.
+pub type Triplets<T> = Vec<(usize, usize, T)>;
+
+/// A sparse matrix taken apart into its number of rows, number of columns and nonzero elements.
+pub type SparseParts<T> = (usize, usize, Triplets<T>);
+
+/// The integer classes Octave knows about.
+/// ```
+/// use mocktave::IntegerClass;
+/// let class: IntegerClass = "uint8".parse().unwrap();
+/// assert_eq!(class, IntegerClass::UInt8);
+/// assert_eq!(class.to_string(), "uint8");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IntegerClass {
+    /// `int8`
+    Int8,
+    /// `int16`
+    Int16,
+    /// `int32`
+    Int32,
+    /// `int64`
+    Int64,
+    /// `uint8`
+    UInt8,
+    /// `uint16`
+    UInt16,
+    /// `uint32`
+    UInt32,
+    /// `uint64`
+    UInt64,
+}
+
+impl IntegerClass {
+    /// The name Octave uses for this class, which is also the function that converts to it.
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegerClass::Int8 => "int8",
+            IntegerClass::Int16 => "int16",
+            IntegerClass::Int32 => "int32",
+            IntegerClass::Int64 => "int64",
+            IntegerClass::UInt8 => "uint8",
+            IntegerClass::UInt16 => "uint16",
+            IntegerClass::UInt32 => "uint32",
+            IntegerClass::UInt64 => "uint64",
+        }
+    }
+}
+
+impl Display for IntegerClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for IntegerClass {
+    type Err = MocktaveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "int8" => Ok(IntegerClass::Int8),
+            "int16" => Ok(IntegerClass::Int16),
+            "int32" => Ok(IntegerClass::Int32),
+            "int64" => Ok(IntegerClass::Int64),
+            "uint8" => Ok(IntegerClass::UInt8),
+            "uint16" => Ok(IntegerClass::UInt16),
+            "uint32" => Ok(IntegerClass::UInt32),
+            "uint64" => Ok(IntegerClass::UInt64),
+            _ => Err(MocktaveError::Parse {
+                message: "Not an integer class".to_string(),
+                text: s.to_string(),
+                line: None,
+                column: None,
+            }),
+        }
+    }
+}
+
 /// Implementation of the Default trait
 /// ```
 /// use mocktave::OctaveType;
@@ -36,36 +180,17 @@ impl Default for OctaveType {
     }
 }
 
-/// Implementation of the Display trait
+/// Values are displayed as the Octave code that evaluates to them, written by
+/// [`OctaveType::to_octave_literal`].
+/// ```
+/// use mocktave::OctaveType;
+/// let x = OctaveType::Matrix(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+/// assert_eq!(x.to_string(), "[1.0, 2.0; 3.0, 4.0]");
+/// assert_eq!(OctaveType::String("abc".to_string()).to_string(), "\"abc\"");
+/// ```
 impl Display for OctaveType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match &self {
-                OctaveType::Scalar(scalar) => {
-                    format!("{scalar}")
-                }
-                OctaveType::ComplexScalar(im, re) => {
-                    format!("{im}{re:+}i")
-                }
-                OctaveType::Matrix(vec) => {
-                    format!("{vec:?}")
-                }
-                OctaveType::String(string) => {
-                    format!("{string}")
-                }
-                OctaveType::CellArray(ot) => {
-                    format!("{ot:?}")
-                }
-                OctaveType::Empty => {
-                    format!("")
-                }
-                OctaveType::Error(message) => {
-                    format!("Error: {message}")
-                }
-            }
-        )
+        f.write_str(&self.to_octave_literal())
     }
 }
 
@@ -87,7 +212,7 @@ impl OctaveType {
     /// ```
     pub fn try_into_f64(self) -> Result<f64, OctaveTryIntoError> {
         if let OctaveType::Scalar(value) = self {
-            return Ok(value);
+            Ok(value)
         } else {
             Err(OctaveTryIntoError(
                 "This is not an `OctaveType::Scalar` and therefore cannot be converted into f64."
@@ -101,7 +226,7 @@ impl OctaveType {
     /// ```
     pub fn try_into_string(self) -> Result<String, OctaveTryIntoError> {
         if let OctaveType::String(value) = self {
-            return Ok(value);
+            Ok(value)
         } else {
             Err(OctaveTryIntoError(
                 "This is not an instance of `OctaveType::String` and therefore cannot be converted into String."
@@ -109,13 +234,20 @@ impl OctaveType {
             ))
         }
     }
-    /// Unwrap an `OctaveType::Matrix` into an `Vec<Vec<f64>>`
+    /// Unwrap an `OctaveType::Matrix` into an `Vec<Vec<f64>>`. Matrices without rows are read as
+    /// two dimensional [`OctaveType::NdArray`]s to keep their number of columns, and unwrap into
+    /// an empty `Vec` too.
     /// ```
-    /// let x: Vec<Vec<f64>> = mocktave::OctaveType::Matrix(vec![vec![0.0_f64;2];2]).try_into_vec_f64().unwrap();
+    /// use mocktave::{NdArray, OctaveType};
+    /// let x: Vec<Vec<f64>> = OctaveType::Matrix(vec![vec![0.0_f64;2];2]).try_into_vec_f64().unwrap();
+    /// let empty = OctaveType::NdArray(NdArray::new(vec![0, 3], vec![]).unwrap());
+    /// assert_eq!(empty.try_into_vec_f64().unwrap(), Vec::<Vec<f64>>::new());
     /// ```
     pub fn try_into_vec_f64(self) -> Result<Vec<Vec<f64>>, OctaveTryIntoError> {
         if let OctaveType::Matrix(value) = self {
-            return Ok(value);
+            Ok(value)
+        } else if matches!(&self, OctaveType::NdArray(array) if no_rows(array)) {
+            Ok(Vec::new())
         } else {
             Err(OctaveTryIntoError("This is not an instance of `OctaveType::Matrix` and therefore cannot be converted into Vec<Vec<f64>>.".to_string()))
         }
@@ -126,7 +258,7 @@ impl OctaveType {
     /// ```
     pub fn try_into_vec_octave_type(self) -> Result<Vec<Vec<OctaveType>>, OctaveTryIntoError> {
         if let OctaveType::CellArray(value) = self {
-            return Ok(value);
+            Ok(value)
         } else {
             Err(OctaveTryIntoError("This is not an instance of `OctaveType::CellArray` and therefore cannot be converted into Vec<Vec<OctaveType>>.".to_string()))
         }
@@ -137,40 +269,353 @@ impl OctaveType {
     /// ```
     pub fn try_into_tuple_f64(self) -> Result<(f64, f64), OctaveTryIntoError> {
         if let OctaveType::ComplexScalar(im, re) = self {
-            return Ok((im, re));
+            Ok((im, re))
         } else {
             Err(OctaveTryIntoError("This is not an instance of `OctaveType::CellArray` and therefore cannot be converted into Vec<Vec<OctaveType>>.".to_string()))
         }
     }
+    /// Unwrap an `OctaveType::Integer` into an `i128`
+    /// ```
+    /// use mocktave::{IntegerClass, OctaveType};
+    /// let x: i128 = OctaveType::Integer(IntegerClass::Int32, 5).try_into_i128().unwrap();
+    /// ```
+    pub fn try_into_i128(self) -> Result<i128, OctaveTryIntoError> {
+        if let OctaveType::Integer(_, value) = self {
+            Ok(value)
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::Integer` and therefore cannot be converted into i128.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::IntegerMatrix` into a `Vec<Vec<i128>>`. Like
+    /// [`OctaveType::try_into_vec_f64`], this also unwraps integer matrices without rows.
+    /// ```
+    /// use mocktave::{IntegerClass, OctaveType};
+    /// let x: Vec<Vec<i128>> = OctaveType::IntegerMatrix(IntegerClass::UInt8, vec![vec![1; 2]; 2]).try_into_vec_i128().unwrap();
+    /// ```
+    pub fn try_into_vec_i128(self) -> Result<Vec<Vec<i128>>, OctaveTryIntoError> {
+        if let OctaveType::IntegerMatrix(_, value) = self {
+            Ok(value)
+        } else if matches!(&self, OctaveType::IntegerNdArray(_, array) if no_rows(array)) {
+            Ok(Vec::new())
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::IntegerMatrix` and therefore cannot be converted into Vec<Vec<i128>>.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::ComplexMatrix` into a `Vec<Vec<(f64, f64)>>`
+    /// ```
+    /// let x: Vec<Vec<(f64, f64)>> = mocktave::OctaveType::ComplexMatrix(vec![vec![(1.0, 1.0); 2]; 2]).try_into_vec_tuple_f64().unwrap();
+    /// ```
+    pub fn try_into_vec_tuple_f64(self) -> Result<Vec<Vec<(f64, f64)>>, OctaveTryIntoError> {
+        if let OctaveType::ComplexMatrix(value) = self {
+            Ok(value)
+        } else if matches!(&self, OctaveType::ComplexNdArray(array) if no_rows(array)) {
+            Ok(Vec::new())
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::ComplexMatrix` and therefore cannot be converted into Vec<Vec<(f64, f64)>>.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::NdArray` into an `NdArray<f64>`
+    /// ```
+    /// use mocktave::{NdArray, OctaveType};
+    /// let x: NdArray<f64> = OctaveType::NdArray(NdArray::new(vec![1, 1, 2], vec![0.0; 2]).unwrap()).try_into_nd_f64().unwrap();
+    /// ```
+    pub fn try_into_nd_f64(self) -> Result<NdArray<f64>, OctaveTryIntoError> {
+        if let OctaveType::NdArray(value) = self {
+            Ok(value)
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::NdArray` and therefore cannot be converted into NdArray<f64>.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::ComplexNdArray` into an `NdArray<(f64, f64)>`
+    /// ```
+    /// use mocktave::{NdArray, OctaveType};
+    /// let x: NdArray<(f64, f64)> = OctaveType::ComplexNdArray(NdArray::new(vec![1, 1, 2], vec![(0.0, 1.0); 2]).unwrap()).try_into_nd_tuple_f64().unwrap();
+    /// ```
+    pub fn try_into_nd_tuple_f64(self) -> Result<NdArray<(f64, f64)>, OctaveTryIntoError> {
+        if let OctaveType::ComplexNdArray(value) = self {
+            Ok(value)
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::ComplexNdArray` and therefore cannot be converted into NdArray<(f64, f64)>.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::BoolNdArray` into an `NdArray<bool>`
+    /// ```
+    /// use mocktave::{NdArray, OctaveType};
+    /// let x: NdArray<bool> = OctaveType::BoolNdArray(NdArray::new(vec![1, 1, 2], vec![true; 2]).unwrap()).try_into_nd_bool().unwrap();
+    /// ```
+    pub fn try_into_nd_bool(self) -> Result<NdArray<bool>, OctaveTryIntoError> {
+        if let OctaveType::BoolNdArray(value) = self {
+            Ok(value)
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::BoolNdArray` and therefore cannot be converted into NdArray<bool>.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::IntegerNdArray` into an `NdArray<i128>`
+    /// ```
+    /// use mocktave::{IntegerClass, NdArray, OctaveType};
+    /// let x: NdArray<i128> = OctaveType::IntegerNdArray(IntegerClass::Int8, NdArray::new(vec![1, 1, 2], vec![1; 2]).unwrap()).try_into_nd_i128().unwrap();
+    /// ```
+    pub fn try_into_nd_i128(self) -> Result<NdArray<i128>, OctaveTryIntoError> {
+        if let OctaveType::IntegerNdArray(_, value) = self {
+            Ok(value)
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::IntegerNdArray` and therefore cannot be converted into NdArray<i128>.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::SparseMatrix` into its number of rows, number of columns and
+    /// nonzero elements
+    /// ```
+    /// use mocktave::OctaveType;
+    /// let x = OctaveType::SparseMatrix { rows: 2, cols: 2, triplets: vec![(0, 0, 1.0)] };
+    /// let (rows, cols, triplets) = x.try_into_sparse_f64().unwrap();
+    /// ```
+    pub fn try_into_sparse_f64(self) -> Result<SparseParts<f64>, OctaveTryIntoError> {
+        if let OctaveType::SparseMatrix {
+            rows,
+            cols,
+            triplets,
+        } = self
+        {
+            Ok((rows, cols, triplets))
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::SparseMatrix` and therefore cannot be converted into SparseParts<f64>.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::SparseComplexMatrix` into its number of rows, number of columns and
+    /// nonzero elements
+    /// ```
+    /// use mocktave::OctaveType;
+    /// let x = OctaveType::SparseComplexMatrix { rows: 2, cols: 2, triplets: vec![(0, 0, (1.0, 1.0))] };
+    /// let (rows, cols, triplets) = x.try_into_sparse_tuple_f64().unwrap();
+    /// ```
+    pub fn try_into_sparse_tuple_f64(self) -> Result<SparseParts<(f64, f64)>, OctaveTryIntoError> {
+        if let OctaveType::SparseComplexMatrix {
+            rows,
+            cols,
+            triplets,
+        } = self
+        {
+            Ok((rows, cols, triplets))
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::SparseComplexMatrix` and therefore cannot be converted into SparseParts<(f64, f64)>.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::SparseBoolMatrix` into its number of rows, number of columns and
+    /// nonzero elements
+    /// ```
+    /// use mocktave::OctaveType;
+    /// let x = OctaveType::SparseBoolMatrix { rows: 2, cols: 2, triplets: vec![(0, 0, true)] };
+    /// let (rows, cols, triplets) = x.try_into_sparse_bool().unwrap();
+    /// ```
+    pub fn try_into_sparse_bool(self) -> Result<SparseParts<bool>, OctaveTryIntoError> {
+        if let OctaveType::SparseBoolMatrix {
+            rows,
+            cols,
+            triplets,
+        } = self
+        {
+            Ok((rows, cols, triplets))
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::SparseBoolMatrix` and therefore cannot be converted into SparseParts<bool>.".to_string()))
+        }
+    }
+    /// Turn a sparse matrix into the equivalent `OctaveType::Matrix`, `OctaveType::ComplexMatrix`
+    /// or `OctaveType::BoolMatrix`. Anything that isn't sparse is returned as it is.
+    /// ```
+    /// use mocktave::OctaveType;
+    /// let x = OctaveType::SparseMatrix { rows: 2, cols: 2, triplets: vec![(1, 0, 3.0)] };
+    /// assert_eq!(x.densify(), OctaveType::Matrix(vec![vec![0.0, 0.0], vec![3.0, 0.0]]));
+    /// ```
+    pub fn densify(self) -> OctaveType {
+        fn dense<T: Clone>(
+            rows: usize,
+            cols: usize,
+            zero: T,
+            triplets: Triplets<T>,
+        ) -> Vec<Vec<T>> {
+            let mut matrix = vec![vec![zero; cols]; rows];
+            for (i, j, v) in triplets {
+                matrix[i][j] = v;
+            }
+            matrix
+        }
+
+        match self {
+            OctaveType::SparseMatrix {
+                rows,
+                cols,
+                triplets,
+            } => OctaveType::Matrix(dense(rows, cols, 0.0, triplets)),
+            OctaveType::SparseComplexMatrix {
+                rows,
+                cols,
+                triplets,
+            } => OctaveType::ComplexMatrix(dense(rows, cols, (0.0, 0.0), triplets)),
+            OctaveType::SparseBoolMatrix {
+                rows,
+                cols,
+                triplets,
+            } => OctaveType::BoolMatrix(dense(rows, cols, false, triplets)),
+            value => value,
+        }
+    }
+    /// Unwrap an `OctaveType::Bool` into a `bool`
+    /// ```
+    /// let x: bool = mocktave::OctaveType::Bool(true).try_into_bool().unwrap();
+    /// ```
+    pub fn try_into_bool(self) -> Result<bool, OctaveTryIntoError> {
+        if let OctaveType::Bool(value) = self {
+            Ok(value)
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::Bool` and therefore cannot be converted into bool.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::BoolMatrix` into a `Vec<Vec<bool>>`
+    /// ```
+    /// let x: Vec<Vec<bool>> = mocktave::OctaveType::BoolMatrix(vec![vec![true; 2]; 2]).try_into_vec_bool().unwrap();
+    /// ```
+    pub fn try_into_vec_bool(self) -> Result<Vec<Vec<bool>>, OctaveTryIntoError> {
+        if let OctaveType::BoolMatrix(value) = self {
+            Ok(value)
+        } else if matches!(&self, OctaveType::BoolNdArray(array) if no_rows(array)) {
+            Ok(Vec::new())
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::BoolMatrix` and therefore cannot be converted into Vec<Vec<bool>>.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::Struct` into a `StructFields`
+    /// ```
+    /// use mocktave::{OctaveType, StructFields};
+    /// let x: StructFields = OctaveType::Struct(vec![("a".to_string(), OctaveType::Scalar(1.0))]).try_into_struct().unwrap();
+    /// ```
+    pub fn try_into_struct(self) -> Result<StructFields, OctaveTryIntoError> {
+        if let OctaveType::Struct(value) = self {
+            Ok(value)
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::Struct` and therefore cannot be converted into Vec<(String, OctaveType)>.".to_string()))
+        }
+    }
+    /// Unwrap an `OctaveType::StructArray` into a `Vec<Vec<StructFields>>`
+    /// ```
+    /// use mocktave::{OctaveType, StructFields};
+    /// let x: Vec<Vec<StructFields>> = OctaveType::StructArray(vec![vec![vec![]; 2]]).try_into_struct_array().unwrap();
+    /// ```
+    pub fn try_into_struct_array(self) -> Result<Vec<Vec<StructFields>>, OctaveTryIntoError> {
+        if let OctaveType::StructArray(value) = self {
+            Ok(value)
+        } else {
+            Err(OctaveTryIntoError("This is not an instance of `OctaveType::StructArray` and therefore cannot be converted into Vec<Vec<StructFields>>.".to_string()))
+        }
+    }
+    /// Look up a field of an `OctaveType::Struct` by name, if this is a struct with that field
+    /// ```
+    /// use mocktave::OctaveType;
+    /// let s = OctaveType::Struct(vec![("a".to_string(), OctaveType::Scalar(1.0))]);
+    /// assert_eq!(s.field("a"), Some(&OctaveType::Scalar(1.0)));
+    /// assert_eq!(s.field("b"), None);
+    /// ```
+    pub fn field(&self, name: &str) -> Option<&OctaveType> {
+        if let OctaveType::Struct(fields) = self {
+            fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value)
+        } else {
+            None
+        }
+    }
+    /// Pick out a single element of an `OctaveType::StructArray` or `OctaveType::CellArray`,
+    /// using Octave's one-based, column-major linear indexing
+    /// ```
+    /// use mocktave::OctaveType;
+    /// let c = OctaveType::CellArray(vec![
+    ///     vec![OctaveType::Scalar(1.0), OctaveType::Scalar(2.0)],
+    ///     vec![OctaveType::Scalar(3.0), OctaveType::Scalar(4.0)],
+    /// ]);
+    /// assert_eq!(c.element(2), Some(OctaveType::Scalar(3.0)));
+    /// ```
+    pub fn element(&self, index: usize) -> Option<OctaveType> {
+        match self {
+            OctaveType::StructArray(array) => {
+                let (row, column) = linear_index(array.len(), index)?;
+                Some(OctaveType::Struct(array.get(row)?.get(column)?.clone()))
+            }
+            OctaveType::CellArray(cell) => {
+                let (row, column) = linear_index(cell.len(), index)?;
+                cell.get(row)?.get(column).cloned()
+            }
+            _ => None,
+        }
+    }
     /// Unwrap an `OctaveType::Empty` into an `()`
     /// ```
     /// let x: () = mocktave::OctaveType::default().try_into_empty().unwrap();
     /// ```
     pub fn try_into_empty(self) -> Result<(), OctaveTryIntoError> {
         if let OctaveType::Empty = self {
-            return Ok(());
+            Ok(())
         } else {
             Err(OctaveTryIntoError("This is not an instance of OctaveType::Empty and therefore cannot be converted into ().".to_string()))
         }
     }
 }
 
-/// Mark a lot of Primitive types
-trait Primitive {}
-impl Primitive for f32 {}
-impl Primitive for f64 {}
-impl Primitive for isize {}
-impl Primitive for i8 {}
-impl Primitive for i16 {}
-impl Primitive for i32 {}
-impl Primitive for i64 {}
-impl Primitive for i128 {}
-impl Primitive for usize {}
-impl Primitive for u8 {}
-impl Primitive for u16 {}
-impl Primitive for u32 {}
-impl Primitive for u64 {}
-impl Primitive for u128 {}
+/// Turn a one-based, column-major linear index into a row and column.
+fn linear_index(rows: usize, index: usize) -> Option<(usize, usize)> {
+    let index = index.checked_sub(1)?;
+    if rows == 0 {
+        None
+    } else {
+        Some((index % rows, index / rows))
+    }
+}
+
+/// Mark a lot of Primitive types, along with the Octave integer class they map onto
+trait Primitive {
+    const CLASS: Option<IntegerClass>;
+}
+impl Primitive for f32 {
+    const CLASS: Option<IntegerClass> = None;
+}
+impl Primitive for f64 {
+    const CLASS: Option<IntegerClass> = None;
+}
+impl Primitive for isize {
+    const CLASS: Option<IntegerClass> = Some(IntegerClass::Int64);
+}
+impl Primitive for i8 {
+    const CLASS: Option<IntegerClass> = Some(IntegerClass::Int8);
+}
+impl Primitive for i16 {
+    const CLASS: Option<IntegerClass> = Some(IntegerClass::Int16);
+}
+impl Primitive for i32 {
+    const CLASS: Option<IntegerClass> = Some(IntegerClass::Int32);
+}
+impl Primitive for i64 {
+    const CLASS: Option<IntegerClass> = Some(IntegerClass::Int64);
+}
+impl Primitive for i128 {
+    const CLASS: Option<IntegerClass> = None;
+}
+impl Primitive for usize {
+    const CLASS: Option<IntegerClass> = Some(IntegerClass::UInt64);
+}
+impl Primitive for u8 {
+    const CLASS: Option<IntegerClass> = Some(IntegerClass::UInt8);
+}
+impl Primitive for u16 {
+    const CLASS: Option<IntegerClass> = Some(IntegerClass::UInt16);
+}
+impl Primitive for u32 {
+    const CLASS: Option<IntegerClass> = Some(IntegerClass::UInt32);
+}
+impl Primitive for u64 {
+    const CLASS: Option<IntegerClass> = Some(IntegerClass::UInt64);
+}
+impl Primitive for u128 {
+    const CLASS: Option<IntegerClass> = None;
+}
 
 /// Convert an `OctaveType::Empty` into an '()'
 /// ```
@@ -179,9 +624,7 @@ impl Primitive for u128 {}
 /// assert_eq!((), x);
 /// ```
 impl From<OctaveType> for () {
-    fn from(_value: OctaveType) -> Self {
-        ()
-    }
+    fn from(_value: OctaveType) -> Self {}
 }
 
 /// Convert an `()` into an `OctaveType::Empty`
@@ -196,7 +639,7 @@ impl From<()> for OctaveType {
     }
 }
 
-/// Convert an `OctaveType::Scalar` into an 'f32'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into an `f32`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: f32 = OctaveType::Scalar(1.0).into();
@@ -204,7 +647,10 @@ impl From<()> for OctaveType {
 /// ```
 impl From<OctaveType> for f32 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as f32
+        match value {
+            OctaveType::Integer(_, integer) => integer as f32,
+            value => value.try_into_f64().unwrap() as f32,
+        }
     }
 }
 
@@ -220,7 +666,7 @@ impl From<f32> for OctaveType {
     }
 }
 
-/// Convert an `OctaveType::Scalar` into an 'f64'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into an `f64`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: f64 = OctaveType::Scalar(1.0).into();
@@ -228,7 +674,10 @@ impl From<f32> for OctaveType {
 /// ```
 impl From<OctaveType> for f64 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap()
+        match value {
+            OctaveType::Integer(_, integer) => integer as f64,
+            value => value.try_into_f64().unwrap(),
+        }
     }
 }
 
@@ -244,7 +693,7 @@ impl From<f64> for OctaveType {
     }
 }
 
-/// Convert an `OctaveType::Scalar` into a `u64`
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into an `isize`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: isize = OctaveType::Scalar(1.0).into();
@@ -252,23 +701,26 @@ impl From<f64> for OctaveType {
 /// ```
 impl From<OctaveType> for isize {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as isize
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as isize,
+        }
     }
 }
 
-/// Convert a `isize` into an `OctaveType::Scalar`
+/// Convert an `isize` into an `OctaveType::Integer` of class `int64`
 /// ```
-/// use mocktave::OctaveType;
+/// use mocktave::{IntegerClass, OctaveType};
 /// let x: OctaveType = 1_isize.into();
-/// assert_eq!(x, OctaveType::Scalar(1.0))
+/// assert_eq!(x, OctaveType::Integer(IntegerClass::Int64, 1))
 /// ```
 impl From<isize> for OctaveType {
     fn from(value: isize) -> Self {
-        OctaveType::Scalar(value as f64)
+        OctaveType::Integer(IntegerClass::Int64, value as i128)
     }
 }
 
-/// Convert an `OctaveType::Scalar` into an 'i8'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into an `i8`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: i8 = OctaveType::Scalar(1.0).into();
@@ -276,23 +728,26 @@ impl From<isize> for OctaveType {
 /// ```
 impl From<OctaveType> for i8 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as i8
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as i8,
+        }
     }
 }
 
-/// Convert a `i8` into an `OctaveType::Scalar`
+/// Convert an `i8` into an `OctaveType::Integer` of class `int8`
 /// ```
-/// use mocktave::OctaveType;
+/// use mocktave::{IntegerClass, OctaveType};
 /// let x: OctaveType = 1_i8.into();
-/// assert_eq!(x, OctaveType::Scalar(1.0))
+/// assert_eq!(x, OctaveType::Integer(IntegerClass::Int8, 1))
 /// ```
 impl From<i8> for OctaveType {
     fn from(value: i8) -> Self {
-        OctaveType::Scalar(value as f64)
+        OctaveType::Integer(IntegerClass::Int8, value as i128)
     }
 }
 
-/// Convert an `OctaveType::Scalar` into an 'i16'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into an `i16`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: i16 = OctaveType::Scalar(1.0).into();
@@ -300,47 +755,58 @@ impl From<i8> for OctaveType {
 /// ```
 impl From<OctaveType> for i16 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as i16
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as i16,
+        }
     }
 }
 
-/// Convert a `i16` into an `OctaveType::Scalar`
+/// Convert an `i16` into an `OctaveType::Integer` of class `int16`
 /// ```
-/// use mocktave::OctaveType;
+/// use mocktave::{IntegerClass, OctaveType};
 /// let x: OctaveType = 1_i16.into();
-/// assert_eq!(x, OctaveType::Scalar(1.0))
+/// assert_eq!(x, OctaveType::Integer(IntegerClass::Int16, 1))
 /// ```
 impl From<i16> for OctaveType {
     fn from(value: i16) -> Self {
-        OctaveType::Scalar(value as f64)
+        OctaveType::Integer(IntegerClass::Int16, value as i128)
     }
 }
 
-/// Convert an `OctaveType::Scalar` into an 'i32'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into an `i32`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: i32 = OctaveType::Scalar(1.0).into();
 /// assert_eq!(x, 1_i32);
 /// ```
+/// Integers that don't fit panic rather than wrap around
+/// ```should_panic
+/// use mocktave::{IntegerClass, OctaveType};
+/// let x: i32 = OctaveType::Integer(IntegerClass::Int64, 1 << 40).into();
+/// ```
 impl From<OctaveType> for i32 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as i32
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as i32,
+        }
     }
 }
 
-/// Convert a `i32` into an `OctaveType::Scalar`
+/// Convert an `i32` into an `OctaveType::Integer` of class `int32`
 /// ```
-/// use mocktave::OctaveType;
+/// use mocktave::{IntegerClass, OctaveType};
 /// let x: OctaveType = 1_i32.into();
-/// assert_eq!(x, OctaveType::Scalar(1.0))
+/// assert_eq!(x, OctaveType::Integer(IntegerClass::Int32, 1))
 /// ```
 impl From<i32> for OctaveType {
     fn from(value: i32) -> Self {
-        OctaveType::Scalar(value as f64)
+        OctaveType::Integer(IntegerClass::Int32, value as i128)
     }
 }
 
-/// Convert an `OctaveType::Scalar` into an 'i64'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into an `i64`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: i64 = OctaveType::Scalar(1.0).into();
@@ -348,23 +814,33 @@ impl From<i32> for OctaveType {
 /// ```
 impl From<OctaveType> for i64 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as i64
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as i64,
+        }
     }
 }
 
-/// Convert a `i64` into an `OctaveType::Scalar`
+/// Convert an `i64` into an `OctaveType::Integer` of class `int64`
 /// ```
-/// use mocktave::OctaveType;
+/// use mocktave::{IntegerClass, OctaveType};
 /// let x: OctaveType = 1_i64.into();
-/// assert_eq!(x, OctaveType::Scalar(1.0))
+/// assert_eq!(x, OctaveType::Integer(IntegerClass::Int64, 1))
+/// ```
+/// Large values survive the round trip
+/// ```
+/// use mocktave::OctaveType;
+/// let x: OctaveType = i64::MAX.into();
+/// let y: i64 = x.into();
+/// assert_eq!(y, i64::MAX)
 /// ```
 impl From<i64> for OctaveType {
     fn from(value: i64) -> Self {
-        OctaveType::Scalar(value as f64)
+        OctaveType::Integer(IntegerClass::Int64, value as i128)
     }
 }
 
-/// Convert an `OctaveType::Scalar` into an 'i128'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into an `i128`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: i128 = OctaveType::Scalar(1.0).into();
@@ -372,11 +848,14 @@ impl From<i64> for OctaveType {
 /// ```
 impl From<OctaveType> for i128 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as i128
+        match value {
+            OctaveType::Integer(_, integer) => integer,
+            value => value.try_into_f64().unwrap() as i128,
+        }
     }
 }
 
-/// Convert a `i128` into an `OctaveType::Scalar`
+/// Convert an `i128` into an `OctaveType::Scalar`, since Octave has no 128 bit integers
 /// ```
 /// use mocktave::OctaveType;
 /// let x: OctaveType = 1_i128.into();
@@ -388,7 +867,7 @@ impl From<i128> for OctaveType {
     }
 }
 
-/// Convert an `OctaveType::Scalar` into a 'usize'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into a `usize`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: usize = OctaveType::Scalar(1.0).into();
@@ -396,23 +875,26 @@ impl From<i128> for OctaveType {
 /// ```
 impl From<OctaveType> for usize {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as usize
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as usize,
+        }
     }
 }
 
-/// Convert a `usize` into an `OctaveType::Scalar`
+/// Convert a `usize` into an `OctaveType::Integer` of class `uint64`
 /// ```
-/// use mocktave::OctaveType;
+/// use mocktave::{IntegerClass, OctaveType};
 /// let x: OctaveType = 1_usize.into();
-/// assert_eq!(x, OctaveType::Scalar(1.0))
+/// assert_eq!(x, OctaveType::Integer(IntegerClass::UInt64, 1))
 /// ```
 impl From<usize> for OctaveType {
     fn from(value: usize) -> Self {
-        OctaveType::Scalar(value as f64)
+        OctaveType::Integer(IntegerClass::UInt64, value as i128)
     }
 }
 
-/// Convert an `OctaveType::Scalar` into a 'u8'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into a `u8`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: u8 = OctaveType::Scalar(1.0).into();
@@ -420,23 +902,26 @@ impl From<usize> for OctaveType {
 /// ```
 impl From<OctaveType> for u8 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as u8
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as u8,
+        }
     }
 }
 
-/// Convert a `u8` into an `OctaveType::Scalar`
+/// Convert a `u8` into an `OctaveType::Integer` of class `uint8`
 /// ```
-/// use mocktave::OctaveType;
+/// use mocktave::{IntegerClass, OctaveType};
 /// let x: OctaveType = 1_u8.into();
-/// assert_eq!(x, OctaveType::Scalar(1.0))
+/// assert_eq!(x, OctaveType::Integer(IntegerClass::UInt8, 1))
 /// ```
 impl From<u8> for OctaveType {
     fn from(value: u8) -> Self {
-        OctaveType::Scalar(value as f64)
+        OctaveType::Integer(IntegerClass::UInt8, value as i128)
     }
 }
 
-/// Convert an `OctaveType::Scalar` into a 'u16'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into a `u16`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: u16 = OctaveType::Scalar(1.0).into();
@@ -444,23 +929,26 @@ impl From<u8> for OctaveType {
 /// ```
 impl From<OctaveType> for u16 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as u16
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as u16,
+        }
     }
 }
 
-/// Convert a `u16` into an `OctaveType::Scalar`
+/// Convert a `u16` into an `OctaveType::Integer` of class `uint16`
 /// ```
-/// use mocktave::OctaveType;
+/// use mocktave::{IntegerClass, OctaveType};
 /// let x: OctaveType = 1_u16.into();
-/// assert_eq!(x, OctaveType::Scalar(1.0))
+/// assert_eq!(x, OctaveType::Integer(IntegerClass::UInt16, 1))
 /// ```
 impl From<u16> for OctaveType {
     fn from(value: u16) -> Self {
-        OctaveType::Scalar(value as f64)
+        OctaveType::Integer(IntegerClass::UInt16, value as i128)
     }
 }
 
-/// Convert an `OctaveType::Scalar` into a 'u32'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into a `u32`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: u32 = OctaveType::Scalar(1.0).into();
@@ -468,23 +956,26 @@ impl From<u16> for OctaveType {
 /// ```
 impl From<OctaveType> for u32 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as u32
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as u32,
+        }
     }
 }
 
-/// Convert a `u32` into an `OctaveType::Scalar`
+/// Convert a `u32` into an `OctaveType::Integer` of class `uint32`
 /// ```
-/// use mocktave::OctaveType;
+/// use mocktave::{IntegerClass, OctaveType};
 /// let x: OctaveType = 1_u32.into();
-/// assert_eq!(x, OctaveType::Scalar(1.0))
+/// assert_eq!(x, OctaveType::Integer(IntegerClass::UInt32, 1))
 /// ```
 impl From<u32> for OctaveType {
     fn from(value: u32) -> Self {
-        OctaveType::Scalar(value as f64)
+        OctaveType::Integer(IntegerClass::UInt32, value as i128)
     }
 }
 
-/// Convert an `OctaveType::Scalar` into a 'u64'
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into a `u64`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: u64 = OctaveType::Scalar(1.0).into();
@@ -492,23 +983,33 @@ impl From<u32> for OctaveType {
 /// ```
 impl From<OctaveType> for u64 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as u64
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as u64,
+        }
     }
 }
 
-/// Convert a `u64` into an `OctaveType::Scalar`
+/// Convert a `u64` into an `OctaveType::Integer` of class `uint64`
 /// ```
-/// use mocktave::OctaveType;
+/// use mocktave::{IntegerClass, OctaveType};
 /// let x: OctaveType = 1_u64.into();
-/// assert_eq!(x, OctaveType::Scalar(1.0))
+/// assert_eq!(x, OctaveType::Integer(IntegerClass::UInt64, 1))
+/// ```
+/// Large values survive the round trip
+/// ```
+/// use mocktave::OctaveType;
+/// let x: OctaveType = u64::MAX.into();
+/// let y: u64 = x.into();
+/// assert_eq!(y, u64::MAX)
 /// ```
 impl From<u64> for OctaveType {
     fn from(value: u64) -> Self {
-        OctaveType::Scalar(value as f64)
+        OctaveType::Integer(IntegerClass::UInt64, value as i128)
     }
 }
 
-/// Convert an `OctaveType::Scalar` into a `u128`
+/// Convert an `OctaveType::Scalar` or `OctaveType::Integer` into a `u128`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: u128 = OctaveType::Scalar(1.0).into();
@@ -516,11 +1017,14 @@ impl From<u64> for OctaveType {
 /// ```
 impl From<OctaveType> for u128 {
     fn from(value: OctaveType) -> Self {
-        value.try_into_f64().unwrap() as u128
+        match value {
+            OctaveType::Integer(class, integer) => narrow(class, integer),
+            value => value.try_into_f64().unwrap() as u128,
+        }
     }
 }
 
-/// Convert a `u128` into an `OctaveType::Scalar`
+/// Convert a `u128` into an `OctaveType::Scalar`, since Octave has no 128 bit integers
 /// ```
 /// use mocktave::OctaveType;
 /// let x: OctaveType = 1_u128.into();
@@ -532,7 +1036,8 @@ impl From<u128> for OctaveType {
     }
 }
 
-/// Convert an `OctaveType::Matrix` into a `Vec<Vec<T>>`
+/// Convert an `OctaveType::Matrix`, `OctaveType::IntegerMatrix` or `OctaveType::SparseMatrix`
+/// into a `Vec<Vec<T>>`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: Vec<Vec<usize>> = OctaveType::Matrix(vec![vec![0.0; 2]; 2]).into();
@@ -547,6 +1052,20 @@ impl From<u128> for OctaveType {
 /// ```
 impl<T: From<OctaveType> + Primitive> From<OctaveType> for Vec<Vec<T>> {
     fn from(value: OctaveType) -> Self {
+        let value = value.densify();
+        if matches!(&value, OctaveType::IntegerNdArray(_, array) if no_rows(array)) {
+            return Vec::new();
+        }
+        if let OctaveType::IntegerMatrix(class, matrix) = value {
+            return matrix
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|el| T::from(OctaveType::Integer(class, el)))
+                        .collect::<Vec<T>>()
+                })
+                .collect::<Vec<Vec<T>>>();
+        }
         value
             .try_into_vec_f64()
             .unwrap()
@@ -560,58 +1079,75 @@ impl<T: From<OctaveType> + Primitive> From<OctaveType> for Vec<Vec<T>> {
     }
 }
 
-/// Convert a `Vec<Vec<T>>` into an `OctaveType::Matrix`
+/// Convert a `Vec<Vec<T>>` into an `OctaveType::Matrix`, or into an `OctaveType::IntegerMatrix`
+/// when `T` has a matching Octave integer class
 /// ```
 /// use mocktave::OctaveType;
 /// let x: OctaveType = vec![vec![0.0; 2]; 2].into();
 /// assert_eq!(x, OctaveType::Matrix(vec![vec![0.0; 2]; 2]))
 /// ```
+/// ```
+/// use mocktave::{IntegerClass, OctaveType};
+/// let x: OctaveType = vec![vec![0_u8; 2]; 2].into();
+/// assert_eq!(x, OctaveType::IntegerMatrix(IntegerClass::UInt8, vec![vec![0; 2]; 2]))
+/// ```
 impl<T: Into<OctaveType> + Primitive> From<Vec<Vec<T>>> for OctaveType {
     fn from(value: Vec<Vec<T>>) -> Self {
-        OctaveType::Matrix(
-            value
-                .into_iter()
-                .map(|row| {
-                    row.into_iter()
-                        .map(|el| el.into().try_into_f64().unwrap())
-                        .collect::<Vec<f64>>()
-                })
-                .collect::<Vec<Vec<f64>>>(),
-        )
+        match T::CLASS {
+            Some(class) => OctaveType::IntegerMatrix(
+                class,
+                value
+                    .into_iter()
+                    .map(|row| {
+                        row.into_iter()
+                            .map(|el| i128::from(el.into()))
+                            .collect::<Vec<i128>>()
+                    })
+                    .collect::<Vec<Vec<i128>>>(),
+            ),
+            None => OctaveType::Matrix(
+                value
+                    .into_iter()
+                    .map(|row| {
+                        row.into_iter()
+                            .map(|el| f64::from(el.into()))
+                            .collect::<Vec<f64>>()
+                    })
+                    .collect::<Vec<Vec<f64>>>(),
+            ),
+        }
     }
 }
 
-/// Convert an `OctaveType::Matrix` into a `Vec<T>`
+/// Convert an `OctaveType::Matrix` or `OctaveType::IntegerMatrix` into a `Vec<T>`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: Vec<usize> = OctaveType::Matrix(vec![vec![0.0; 5]; 1]).into();
-/// assert_eq!(x, vec![0_usize; 5])
+/// assert_eq!(x, vec![0_usize; 5]);
+/// let empty: Vec<f64> = OctaveType::Matrix(vec![]).into();
+/// assert!(empty.is_empty());
 /// ```
 impl<T: Primitive + From<OctaveType>> From<OctaveType> for Vec<T> {
     fn from(value: OctaveType) -> Self {
-        let new: Vec<Vec<f64>> = value.try_into_vec_f64().unwrap();
+        let new: Vec<Vec<T>> = value.into();
 
+        if new.is_empty() {
+            return Vec::new();
+        }
         let w = new.len();
         let h = new[0].len();
 
-        if w == 1 {
-            new[0]
-                .clone()
-                .into_iter()
-                .map(|el| T::from(OctaveType::Scalar(el)))
-                .collect::<Vec<T>>()
-        } else if h == 1 {
-            new.clone()
-                .into_iter()
-                .map(|el| T::from(OctaveType::Scalar(el[0])))
-                .collect::<Vec<T>>()
+        if w == 1 || h == 1 {
+            new.into_iter().flatten().collect::<Vec<T>>()
         } else {
-            panic!()
+            panic!(
+                "Only a row or column vector can be converted into a Vec<T>, not a {w}x{h} matrix."
+            )
         }
     }
 }
 
-/// Convert a `Vec<T>` into an `OctaveType::Matrix`
+/// Convert a `Vec<T>` into a single row `OctaveType::Matrix` or `OctaveType::IntegerMatrix`
 /// ```
 /// use mocktave::OctaveType;
 /// let x: OctaveType = vec![0.0; 5].into();
@@ -619,10 +1155,112 @@ impl<T: Primitive + From<OctaveType>> From<OctaveType> for Vec<T> {
 /// ```
 impl<T: Into<OctaveType> + Primitive> From<Vec<T>> for OctaveType {
     fn from(value: Vec<T>) -> Self {
-        OctaveType::Matrix(vec![value
-            .into_iter()
-            .map(|el| el.into().try_into_f64().unwrap())
-            .collect::<Vec<f64>>()])
+        OctaveType::from(vec![value])
+    }
+}
+
+/// Convert an `OctaveType::NdArray`, `OctaveType::IntegerNdArray`, `OctaveType::Matrix` or
+/// `OctaveType::IntegerMatrix` into an `NdArray<T>`
+/// ```
+/// use mocktave::{NdArray, OctaveType};
+/// let x: NdArray<usize> = OctaveType::NdArray(NdArray::new(vec![1, 2, 2], vec![1.0; 4]).unwrap()).into();
+/// assert_eq!(x.shape(), &[1, 2, 2]);
+/// let (data, shape) = x.into_parts();
+/// assert_eq!(data, vec![1_usize; 4]);
+/// assert_eq!(shape, vec![1, 2, 2]);
+/// ```
+/// Matrices come out as two dimensional arrays
+/// ```
+/// use mocktave::{NdArray, OctaveType};
+/// let x: NdArray<f64> = OctaveType::Matrix(vec![vec![1.0, 2.0]]).into();
+/// assert_eq!(x.shape(), &[1, 2]);
+/// ```
+impl<T: Primitive + From<OctaveType>> From<OctaveType> for NdArray<T> {
+    fn from(value: OctaveType) -> Self {
+        match value {
+            OctaveType::NdArray(array) => array.map(|el| T::from(OctaveType::Scalar(el))),
+            OctaveType::IntegerNdArray(class, array) => {
+                array.map(|el| T::from(OctaveType::Integer(class, el)))
+            }
+            OctaveType::IntegerMatrix(class, matrix) => {
+                NdArray::from(matrix).map(|el| T::from(OctaveType::Integer(class, el)))
+            }
+            value => NdArray::from(value.try_into_vec_f64().unwrap())
+                .map(|el| T::from(OctaveType::Scalar(el))),
+        }
+    }
+}
+
+/// Convert an `NdArray<T>` into an `OctaveType::NdArray`, or into an `OctaveType::IntegerNdArray`
+/// when `T` has a matching Octave integer class
+/// ```
+/// use mocktave::{IntegerClass, NdArray, OctaveType};
+/// let x: OctaveType = NdArray::new(vec![1, 1, 2], vec![1.0, 2.0]).unwrap().into();
+/// assert_eq!(x, OctaveType::NdArray(NdArray::new(vec![1, 1, 2], vec![1.0, 2.0]).unwrap()));
+/// let y: OctaveType = NdArray::new(vec![1, 1, 2], vec![1_u8, 2]).unwrap().into();
+/// assert_eq!(y, OctaveType::IntegerNdArray(IntegerClass::UInt8, NdArray::new(vec![1, 1, 2], vec![1, 2]).unwrap()));
+/// ```
+impl<T: Into<OctaveType> + Primitive> From<NdArray<T>> for OctaveType {
+    fn from(value: NdArray<T>) -> Self {
+        match T::CLASS {
+            Some(class) => OctaveType::IntegerNdArray(class, value.map(|el| i128::from(el.into()))),
+            None => OctaveType::NdArray(value.map(|el| f64::from(el.into()))),
+        }
+    }
+}
+
+/// Convert an `OctaveType::ComplexNdArray` or `OctaveType::ComplexMatrix` into an
+/// `NdArray<(f64, f64)>`
+/// ```
+/// use mocktave::{NdArray, OctaveType};
+/// let x: NdArray<(f64, f64)> = OctaveType::ComplexMatrix(vec![vec![(1.0, 2.0)]]).into();
+/// assert_eq!(x.data(), &[(1.0, 2.0)]);
+/// ```
+impl From<OctaveType> for NdArray<(f64, f64)> {
+    fn from(value: OctaveType) -> Self {
+        match value {
+            OctaveType::ComplexNdArray(array) => array,
+            value => NdArray::from(value.try_into_vec_tuple_f64().unwrap()),
+        }
+    }
+}
+
+/// Convert an `NdArray<(f64, f64)>` into an `OctaveType::ComplexNdArray`
+/// ```
+/// use mocktave::{NdArray, OctaveType};
+/// let x: OctaveType = NdArray::new(vec![1, 1, 2], vec![(1.0, 2.0); 2]).unwrap().into();
+/// assert_eq!(x, OctaveType::ComplexNdArray(NdArray::new(vec![1, 1, 2], vec![(1.0, 2.0); 2]).unwrap()));
+/// ```
+impl From<NdArray<(f64, f64)>> for OctaveType {
+    fn from(value: NdArray<(f64, f64)>) -> Self {
+        OctaveType::ComplexNdArray(value)
+    }
+}
+
+/// Convert an `OctaveType::BoolNdArray` or `OctaveType::BoolMatrix` into an `NdArray<bool>`
+/// ```
+/// use mocktave::{NdArray, OctaveType};
+/// let x: NdArray<bool> = OctaveType::BoolMatrix(vec![vec![true], vec![false]]).into();
+/// assert_eq!(x.shape(), &[2, 1]);
+/// ```
+impl From<OctaveType> for NdArray<bool> {
+    fn from(value: OctaveType) -> Self {
+        match value {
+            OctaveType::BoolNdArray(array) => array,
+            value => NdArray::from(value.try_into_vec_bool().unwrap()),
+        }
+    }
+}
+
+/// Convert an `NdArray<bool>` into an `OctaveType::BoolNdArray`
+/// ```
+/// use mocktave::{NdArray, OctaveType};
+/// let x: OctaveType = NdArray::new(vec![1, 1, 2], vec![true, false]).unwrap().into();
+/// assert_eq!(x, OctaveType::BoolNdArray(NdArray::new(vec![1, 1, 2], vec![true, false]).unwrap()));
+/// ```
+impl From<NdArray<bool>> for OctaveType {
+    fn from(value: NdArray<bool>) -> Self {
+        OctaveType::BoolNdArray(value)
     }
 }
 
@@ -653,9 +1291,128 @@ impl<T: Primitive + From<OctaveType>> From<OctaveType> for (T, T) {
 /// ```
 impl<T: Into<OctaveType> + Primitive> From<(T, T)> for OctaveType {
     fn from(value: (T, T)) -> Self {
-        OctaveType::ComplexScalar(
-            value.0.into().try_into_f64().unwrap(),
-            value.1.into().try_into_f64().unwrap(),
+        OctaveType::ComplexScalar(f64::from(value.0.into()), f64::from(value.1.into()))
+    }
+}
+
+/// Convert an `OctaveType::ComplexMatrix` into a `Vec<Vec<(T, T)>>`
+/// ```
+/// use mocktave::OctaveType;
+/// let x: Vec<Vec<(f64, f64)>> = OctaveType::ComplexMatrix(vec![vec![(1.0, 2.0); 2]]).into();
+/// assert_eq!(x, vec![vec![(1.0, 2.0); 2]])
+/// ```
+impl<T: Primitive + From<OctaveType>> From<OctaveType> for Vec<Vec<(T, T)>> {
+    fn from(value: OctaveType) -> Self {
+        value
+            .try_into_vec_tuple_f64()
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|(im, re)| {
+                        (
+                            T::from(OctaveType::Scalar(im)),
+                            T::from(OctaveType::Scalar(re)),
+                        )
+                    })
+                    .collect::<Vec<(T, T)>>()
+            })
+            .collect::<Vec<Vec<(T, T)>>>()
+    }
+}
+
+/// Convert a `Vec<Vec<(T, T)>>` into an `OctaveType::ComplexMatrix`
+/// ```
+/// use mocktave::OctaveType;
+/// let x: OctaveType = vec![vec![(1.0, 2.0); 2]].into();
+/// assert_eq!(x, OctaveType::ComplexMatrix(vec![vec![(1.0, 2.0); 2]]))
+/// ```
+impl<T: Into<OctaveType> + Primitive> From<Vec<Vec<(T, T)>>> for OctaveType {
+    fn from(value: Vec<Vec<(T, T)>>) -> Self {
+        OctaveType::ComplexMatrix(
+            value
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|(im, re)| (f64::from(im.into()), f64::from(re.into())))
+                        .collect::<Vec<(f64, f64)>>()
+                })
+                .collect::<Vec<Vec<(f64, f64)>>>(),
+        )
+    }
+}
+
+/// Convert an `OctaveType::ComplexScalar` into a `num_complex::Complex64`
+/// ```
+/// use mocktave::OctaveType;
+/// use num_complex::Complex64;
+/// let x: Complex64 = OctaveType::ComplexScalar(1.0, 2.0).into();
+/// assert_eq!(x, Complex64::new(1.0, 2.0))
+/// ```
+#[cfg(feature = "num-complex")]
+impl From<OctaveType> for num_complex::Complex64 {
+    fn from(value: OctaveType) -> Self {
+        let (re, im) = value.try_into_tuple_f64().unwrap();
+        num_complex::Complex64::new(re, im)
+    }
+}
+
+/// Convert a `num_complex::Complex64` into an `OctaveType::ComplexScalar`
+/// ```
+/// use mocktave::OctaveType;
+/// use num_complex::Complex64;
+/// let x: OctaveType = Complex64::new(1.0, 2.0).into();
+/// assert_eq!(x, OctaveType::ComplexScalar(1.0, 2.0))
+/// ```
+#[cfg(feature = "num-complex")]
+impl From<num_complex::Complex64> for OctaveType {
+    fn from(value: num_complex::Complex64) -> Self {
+        OctaveType::ComplexScalar(value.re, value.im)
+    }
+}
+
+/// Convert an `OctaveType::ComplexMatrix` into a `Vec<Vec<num_complex::Complex64>>`
+/// ```
+/// use mocktave::OctaveType;
+/// use num_complex::Complex64;
+/// let x: Vec<Vec<Complex64>> = OctaveType::ComplexMatrix(vec![vec![(1.0, 2.0); 2]]).into();
+/// assert_eq!(x, vec![vec![Complex64::new(1.0, 2.0); 2]])
+/// ```
+#[cfg(feature = "num-complex")]
+impl From<OctaveType> for Vec<Vec<num_complex::Complex64>> {
+    fn from(value: OctaveType) -> Self {
+        value
+            .try_into_vec_tuple_f64()
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|(re, im)| num_complex::Complex64::new(re, im))
+                    .collect::<Vec<num_complex::Complex64>>()
+            })
+            .collect::<Vec<Vec<num_complex::Complex64>>>()
+    }
+}
+
+/// Convert a `Vec<Vec<num_complex::Complex64>>` into an `OctaveType::ComplexMatrix`
+/// ```
+/// use mocktave::OctaveType;
+/// use num_complex::Complex64;
+/// let x: OctaveType = vec![vec![Complex64::new(1.0, 2.0); 2]].into();
+/// assert_eq!(x, OctaveType::ComplexMatrix(vec![vec![(1.0, 2.0); 2]]))
+/// ```
+#[cfg(feature = "num-complex")]
+impl From<Vec<Vec<num_complex::Complex64>>> for OctaveType {
+    fn from(value: Vec<Vec<num_complex::Complex64>>) -> Self {
+        OctaveType::ComplexMatrix(
+            value
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|element| (element.re, element.im))
+                        .collect::<Vec<(f64, f64)>>()
+                })
+                .collect::<Vec<Vec<(f64, f64)>>>(),
         )
     }
 }
@@ -672,6 +1429,30 @@ impl From<OctaveType> for String {
     }
 }
 
+/// Convert a `String` into an `OctaveType::String`
+/// ```
+/// use mocktave::OctaveType;
+/// let x: OctaveType = "abc".to_string().into();
+/// assert_eq!(x, OctaveType::String("abc".to_string()))
+/// ```
+impl From<String> for OctaveType {
+    fn from(value: String) -> Self {
+        OctaveType::String(value)
+    }
+}
+
+/// Convert a `&str` into an `OctaveType::String`
+/// ```
+/// use mocktave::OctaveType;
+/// let x: OctaveType = "abc".into();
+/// assert_eq!(x, OctaveType::String("abc".to_string()))
+/// ```
+impl From<&str> for OctaveType {
+    fn from(value: &str) -> Self {
+        OctaveType::String(value.to_string())
+    }
+}
+
 /// Convert an `OctaveType::CellArray` into `Vec<Vec<OctaveType>>`
 /// ```
 /// use mocktave::OctaveType;
@@ -683,3 +1464,68 @@ impl From<OctaveType> for Vec<Vec<OctaveType>> {
         value.try_into_vec_octave_type().unwrap()
     }
 }
+
+/// Convert an `OctaveType::Bool` into a `bool`
+/// ```
+/// use mocktave::OctaveType;
+/// let x: bool = OctaveType::Bool(true).into();
+/// assert_eq!(x, true);
+/// ```
+impl From<OctaveType> for bool {
+    fn from(value: OctaveType) -> Self {
+        value.try_into_bool().unwrap()
+    }
+}
+
+/// Convert a `bool` into an `OctaveType::Bool`
+/// ```
+/// use mocktave::OctaveType;
+/// let x: OctaveType = true.into();
+/// assert_eq!(x, OctaveType::Bool(true))
+/// ```
+impl From<bool> for OctaveType {
+    fn from(value: bool) -> Self {
+        OctaveType::Bool(value)
+    }
+}
+
+/// Convert an `OctaveType::BoolMatrix` into a `Vec<Vec<bool>>`
+/// ```
+/// use mocktave::OctaveType;
+/// let x: Vec<Vec<bool>> = OctaveType::BoolMatrix(vec![vec![true, false]]).into();
+/// assert_eq!(x, vec![vec![true, false]]);
+/// ```
+impl From<OctaveType> for Vec<Vec<bool>> {
+    fn from(value: OctaveType) -> Self {
+        value.try_into_vec_bool().unwrap()
+    }
+}
+
+/// Convert a `Vec<Vec<bool>>` into an `OctaveType::BoolMatrix`
+/// ```
+/// use mocktave::OctaveType;
+/// let x: OctaveType = vec![vec![true, false]].into();
+/// assert_eq!(x, OctaveType::BoolMatrix(vec![vec![true, false]]))
+/// ```
+impl From<Vec<Vec<bool>>> for OctaveType {
+    fn from(value: Vec<Vec<bool>>) -> Self {
+        OctaveType::BoolMatrix(value)
+    }
+}
+
+/// Whether `array` is a matrix without rows, like `zeros(0, 3)`. These are kept as arrays so that
+/// their number of columns survives, but convert to matrices like any other empty matrix.
+fn no_rows<T>(array: &NdArray<T>) -> bool {
+    matches!(array.shape(), [0, _])
+}
+
+/// Convert an Octave integer of class `class` into a narrower Rust integer, panicking instead of
+/// wrapping around when it doesn't fit.
+fn narrow<T: TryFrom<i128>>(class: IntegerClass, integer: i128) -> T {
+    T::try_from(integer).unwrap_or_else(|_| {
+        panic!(
+            "The {class} value {integer} does not fit into {}.",
+            std::any::type_name::<T>()
+        )
+    })
+}
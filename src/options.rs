@@ -0,0 +1,23 @@
+//! Settings shared by the blocking and the async interpreter.
+
+use std::time::Duration;
+
+/// How an interpreter runs scripts and reports on them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Options {
+    /// Whether script output is echoed to this process' stdout and stderr.
+    pub(crate) echo: bool,
+    /// Identifiers of the warnings that are raised as errors.
+    pub(crate) promoted: Vec<String>,
+    /// How long a script may run before it is stopped.
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl Options {
+    /// Raise warnings with the identifier `identifier` as errors.
+    pub(crate) fn promote(&mut self, identifier: &str) {
+        if !self.promoted.iter().any(|promoted| promoted == identifier) {
+            self.promoted.push(identifier.to_string());
+        }
+    }
+}
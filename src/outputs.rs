@@ -0,0 +1,52 @@
+use crate::OctaveType;
+
+/// A group of values returned together by an Octave function, as in `[q, r] = qr(A)`. This is
+/// implemented for tuples of up to eight types that each implement `From<OctaveType>`, and the
+/// size of the tuple decides how many outputs are requested from the function.
+/// ```
+/// use mocktave::{OctaveType, Outputs};
+/// assert_eq!(<(f64, String)>::COUNT, 2);
+/// let (a, b): (f64, String) = Outputs::from_outputs(vec![
+///     OctaveType::Scalar(1.0),
+///     OctaveType::String("two".to_string()),
+/// ]);
+/// assert_eq!(a, 1.0);
+/// assert_eq!(b, "two");
+/// ```
+pub trait Outputs {
+    /// The number of outputs, which is passed to Octave as `nargout`
+    const COUNT: usize;
+    /// Build the group from the outputs in the order the function returned them. There must be
+    /// exactly [`Outputs::COUNT`] of them.
+    fn from_outputs(outputs: Vec<OctaveType>) -> Self;
+}
+
+/// No outputs at all, for functions that are only called for their side effects
+impl Outputs for () {
+    const COUNT: usize = 0;
+    fn from_outputs(outputs: Vec<OctaveType>) -> Self {
+        assert!(outputs.is_empty(), "Wrong number of outputs");
+    }
+}
+
+macro_rules! impl_outputs {
+    ($count:expr; $($name:ident),+) => {
+        impl<$($name: From<OctaveType>),+> Outputs for ($($name,)+) {
+            const COUNT: usize = $count;
+            fn from_outputs(outputs: Vec<OctaveType>) -> Self {
+                assert_eq!(outputs.len(), Self::COUNT, "Wrong number of outputs");
+                let mut outputs = outputs.into_iter();
+                ($($name::from(outputs.next().unwrap()),)+)
+            }
+        }
+    };
+}
+
+impl_outputs!(1; Z1);
+impl_outputs!(2; Z1, Z2);
+impl_outputs!(3; Z1, Z2, Z3);
+impl_outputs!(4; Z1, Z2, Z3, Z4);
+impl_outputs!(5; Z1, Z2, Z3, Z4, Z5);
+impl_outputs!(6; Z1, Z2, Z3, Z4, Z5, Z6);
+impl_outputs!(7; Z1, Z2, Z3, Z4, Z5, Z6, Z7);
+impl_outputs!(8; Z1, Z2, Z3, Z4, Z5, Z6, Z7, Z8);
@@ -0,0 +1,320 @@
+//! A set of warm interpreters that are lent out one at a time, for running many scripts in
+//! parallel.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+use crate::builder::InterpreterBuilder;
+use crate::options::Options;
+use crate::{Interpreter, InterpreterResults, MocktaveError, OctaveType};
+
+/// Runs a script on an interpreter, keeping Octave's errors in the results or reporting them.
+type Evaluate = fn(&mut Interpreter, &str) -> Result<InterpreterResults, MocktaveError>;
+
+/// Up to a fixed number of interpreters that are started when they are first needed and then kept
+/// warm, so that threads can share them without paying for a new Octave session every time.
+/// Interpreters are borrowed with [`InterpreterPool::lease`], and when a lease ends the
+/// workspace is cleared and the settings made through the lease are undone before the
+/// interpreter goes back to the pool, including in Octave: warnings promoted through the lease
+/// are plain warnings again. Settings a script changes in Octave itself, like the path or
+/// `format`, carry over to later leases. The pool can be shared between threads by reference or
+/// in an `Arc`.
+/// ```
+/// let pool = mocktave::InterpreterPool::new(2);
+/// std::thread::scope(|scope| {
+///     for n in 1..=4 {
+///         let pool = &pool;
+///         scope.spawn(move || {
+///             let res = pool.lease().eval(&format!("x = {n} * 2"));
+///             assert_eq!(res.get_scalar("x").unwrap(), (n * 2) as f64);
+///         });
+///     }
+/// });
+/// ```
+pub struct InterpreterPool {
+    builder: InterpreterBuilder,
+    options: Options,
+    size: usize,
+    state: Mutex<State>,
+    returned: Condvar,
+}
+
+/// The interpreters of a pool that aren't lent out.
+#[derive(Default)]
+struct State {
+    idle: Vec<Interpreter>,
+    started: usize,
+    /// How many interpreters the pool has started over its lifetime, which numbers them.
+    count: usize,
+}
+
+impl InterpreterPool {
+    /// A pool of up to `size` interpreters started with the default settings. A `size` of zero is
+    /// treated as one. Use [`InterpreterBuilder::build_pool`] for other settings.
+    pub fn new(size: usize) -> Self {
+        InterpreterBuilder::default().build_pool(size)
+    }
+
+    /// A pool of up to `size` interpreters started by `builder`, which run scripts with
+    /// `options` at the start of every lease.
+    pub(crate) fn with_builder(builder: InterpreterBuilder, options: Options, size: usize) -> Self {
+        InterpreterPool {
+            builder,
+            options,
+            size: size.max(1),
+            state: Mutex::new(State::default()),
+            returned: Condvar::new(),
+        }
+    }
+
+    /// The most interpreters the pool runs at once.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Borrow an interpreter, waiting for one to be returned if they are all lent out. Panics if
+    /// a new interpreter is needed and can't be started.
+    /// ```
+    /// let pool = mocktave::InterpreterPool::new(1);
+    /// let mut interp = pool.lease();
+    /// interp.eval("a = 1");
+    /// assert_eq!(interp.eval("b = a + 1").get_scalar("b").unwrap(), 2_f64);
+    /// drop(interp);
+    ///
+    /// // The next lease gets the same interpreter, but with a clean workspace
+    /// let res = pool.lease().eval("exists = exist('a')");
+    /// assert_eq!(res.get_scalar("exists").unwrap(), 0_f64);
+    ///
+    /// // Warnings promoted through a lease are only errors until it ends
+    /// let mut interp = pool.lease();
+    /// interp.promote_warning("mocktave:example");
+    /// let res = interp.eval("state = warning('query', 'mocktave:example').state");
+    /// assert_eq!(res.get_string("state").unwrap(), "error");
+    /// drop(interp);
+    /// let res = pool.lease().eval("state = warning('query', 'mocktave:example').state");
+    /// assert_eq!(res.get_string("state").unwrap(), "on");
+    /// ```
+    pub fn lease(&self) -> PooledInterpreter<'_> {
+        self.try_lease().expect("Could not start the interpreter.")
+    }
+
+    /// Borrow an interpreter like [`InterpreterPool::lease`], but report failures instead of
+    /// panicking.
+    pub fn try_lease(&self) -> Result<PooledInterpreter<'_>, MocktaveError> {
+        let mut state = lock(&self.state);
+        loop {
+            if let Some(interp) = state.idle.pop() {
+                return Ok(PooledInterpreter::new(self, interp));
+            }
+            if state.started < self.size {
+                state.started += 1;
+                state.count += 1;
+                let builder = self.builder.clone().numbered(state.count);
+                drop(state);
+                return match builder.try_build() {
+                    Ok(interp) => Ok(PooledInterpreter::new(self, interp)),
+                    Err(err) => {
+                        self.discard();
+                        Err(err)
+                    }
+                };
+            }
+            state = self
+                .returned
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Run `script` once for every element of `inputs`, spread over the interpreters of the pool,
+    /// and return the results in the same order as `inputs`. Each element holds the variables to
+    /// set before the script runs, given as pairs of names and values, and every run starts from
+    /// a clean workspace. Like [`Interpreter::eval`], errors raised by Octave are kept in the
+    /// results, and anything else panics.
+    /// ```
+    /// let pool = mocktave::InterpreterPool::new(4);
+    /// let script = "
+    ///     opt = odeset('RelTol', tol, 'AbsTol', realmin);
+    ///     [t, y] = ode45(@(t, y) -y, [0, 1], 1, opt);
+    ///     err = norm(y .* exp(t) - 1, Inf);
+    /// ";
+    /// let tolerances = [1e-5, 1e-6, 1e-7, 1e-8];
+    /// let results = pool.map(tolerances.iter().map(|tol| [("tol", *tol)]), script);
+    /// let errors = results
+    ///     .iter()
+    ///     .map(|res| res.get_scalar("err").unwrap())
+    ///     .collect::<Vec<_>>();
+    /// assert!(errors.windows(2).all(|pair| pair[1] < pair[0]));
+    /// ```
+    pub fn map<I, K, V>(&self, inputs: I, script: &str) -> Vec<InterpreterResults>
+    where
+        I: IntoIterator,
+        I::Item: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<OctaveType>,
+    {
+        self.sweep(inputs, script, Interpreter::run)
+            .into_iter()
+            .map(|results| results.expect("Could not evaluate Octave code."))
+            .collect()
+    }
+
+    /// Run a script for every element of `inputs` like [`InterpreterPool::map`], but report
+    /// failures instead of panicking. Errors raised by Octave are returned as
+    /// [`MocktaveError::Octave`], and the first failure in the order of `inputs` is the one
+    /// reported.
+    /// ```
+    /// let pool = mocktave::InterpreterPool::new(2);
+    /// let inputs = [[("a", 4.0)], [("a", -1.0)]];
+    /// let err = pool.try_map(inputs, "r = chol(a)").unwrap_err();
+    /// assert!(matches!(err, mocktave::MocktaveError::Octave { .. }));
+    /// ```
+    pub fn try_map<I, K, V>(
+        &self,
+        inputs: I,
+        script: &str,
+    ) -> Result<Vec<InterpreterResults>, MocktaveError>
+    where
+        I: IntoIterator,
+        I::Item: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<OctaveType>,
+    {
+        self.sweep(inputs, script, Interpreter::try_eval)
+            .into_iter()
+            .collect()
+    }
+
+    /// Run `script` with `evaluate` for every element of `inputs` on as many threads as the pool
+    /// has interpreters, each of which leases an interpreter per element.
+    fn sweep<I, K, V>(
+        &self,
+        inputs: I,
+        script: &str,
+        evaluate: Evaluate,
+    ) -> Vec<Result<InterpreterResults, MocktaveError>>
+    where
+        I: IntoIterator,
+        I::Item: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<OctaveType>,
+    {
+        let inputs = inputs
+            .into_iter()
+            .map(|variables| {
+                variables
+                    .into_iter()
+                    .map(|(name, value)| (name.as_ref().to_string(), value.into()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let workers = self.size.min(inputs.len());
+        let mut results = Vec::with_capacity(inputs.len());
+        results.resize_with(inputs.len(), || None);
+
+        let jobs = Mutex::new(inputs.into_iter().enumerate());
+        let results = Mutex::new(results);
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let Some((index, variables)) = lock(&jobs).next() else {
+                        break;
+                    };
+                    let result = self.try_lease().and_then(|mut interp| {
+                        interp.load(&variables)?;
+                        evaluate(&mut interp, script)
+                    });
+                    lock(&results)[index] = Some(result);
+                });
+            }
+        });
+        results
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .into_iter()
+            .map(|result| result.expect("Every input is run."))
+            .collect()
+    }
+
+    /// Clean up `interp` and put it back, or stop it if it can't be cleaned up.
+    fn give_back(&self, mut interp: Interpreter) {
+        // Running `clear` with the pool's own options also turns warnings promoted through the
+        // lease back into warnings
+        *interp.options() = self.options.clone();
+        let cleared = interp
+            .run("clear")
+            .is_ok_and(|results| results.error().is_none());
+        if cleared {
+            lock(&self.state).idle.push(interp);
+            self.returned.notify_one();
+        } else {
+            drop(interp);
+            self.discard();
+        }
+    }
+
+    /// Stop the interpreters that aren't lent out.
+    pub(crate) fn close_idle(&self) {
+        let idle = {
+            let mut state = lock(&self.state);
+            state.started -= state.idle.len();
+            std::mem::take(&mut state.idle)
+        };
+        drop(idle);
+        self.returned.notify_all();
+    }
+
+    /// Make room for a new interpreter in place of one that is gone.
+    fn discard(&self) {
+        lock(&self.state).started -= 1;
+        self.returned.notify_one();
+    }
+}
+
+/// An interpreter borrowed from an [`InterpreterPool`], which is used through
+/// [`Interpreter`]'s methods and goes back to the pool when dropped.
+pub struct PooledInterpreter<'a> {
+    pool: &'a InterpreterPool,
+    interp: Option<Interpreter>,
+}
+
+impl<'a> PooledInterpreter<'a> {
+    fn new(pool: &'a InterpreterPool, interp: Interpreter) -> Self {
+        PooledInterpreter {
+            pool,
+            interp: Some(interp),
+        }
+    }
+}
+
+impl Deref for PooledInterpreter<'_> {
+    type Target = Interpreter;
+
+    fn deref(&self) -> &Interpreter {
+        self.interp
+            .as_ref()
+            .expect("The interpreter is only taken on drop.")
+    }
+}
+
+impl DerefMut for PooledInterpreter<'_> {
+    fn deref_mut(&mut self) -> &mut Interpreter {
+        self.interp
+            .as_mut()
+            .expect("The interpreter is only taken on drop.")
+    }
+}
+
+impl Drop for PooledInterpreter<'_> {
+    fn drop(&mut self) {
+        if let Some(interp) = self.interp.take() {
+            self.pool.give_back(interp);
+        }
+    }
+}
+
+/// Lock `mutex`, carrying on if a thread panicked while holding it, since every critical section
+/// leaves the state consistent.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
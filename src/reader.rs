@@ -0,0 +1,530 @@
+//! A reader for Octave's text save format that walks through the output one block at a time.
+//!
+//! The reader knows how long every block is, so it can follow values that are nested inside other
+//! values, like the fields of a struct inside a cell, in a single pass. Anything it can't make
+//! sense of is reported with the line and column it happened on.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::{IntegerClass, MocktaveError, NdArray, OctaveType, SparseParts, StructFields};
+
+/// Read every variable in a workspace saved by `save -text`. Anything before the first block,
+/// like the `# Created by` header, is skipped.
+pub(crate) fn workspace(text: &str) -> Result<HashMap<String, OctaveType>, MocktaveError> {
+    let mut reader = Reader::new(text);
+    let mut variables = HashMap::new();
+    while reader.seek_block() {
+        let (name, value) = reader.block().map_err(|error| reader.locate(error))?;
+        if let Some(value) = value {
+            variables.insert(name, value);
+        }
+    }
+    Ok(variables)
+}
+
+/// A cursor into a piece of Octave's text save format.
+struct Reader<'a> {
+    text: &'a str,
+    position: usize,
+    /// Where the line that was read last starts, which is where errors are reported.
+    line_start: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(text: &'a str) -> Self {
+        Reader {
+            text,
+            position: 0,
+            line_start: 0,
+        }
+    }
+
+    /// Skip ahead to the next `# name:` line, returning `false` if there isn't one.
+    fn seek_block(&mut self) -> bool {
+        while self.position < self.text.len() {
+            if self.rest().starts_with("# name: ") {
+                return true;
+            }
+            self.skip_line();
+        }
+        false
+    }
+
+    /// Read a whole `# name:`/`# type:` block. Blocks of a type the reader doesn't know about are
+    /// skipped up to the next blank line and come back as `None`.
+    fn block(&mut self) -> Result<(String, Option<OctaveType>), MocktaveError> {
+        self.skip_blank_lines();
+        let name = self.header("name")?.to_string();
+        let kind = self.header("type")?;
+        let value = self.value(kind)?;
+        if value.is_none() {
+            while self.position < self.text.len() && !self.line()?.trim().is_empty() {}
+        }
+        Ok((name, value))
+    }
+
+    /// Read the body of a block whose type is `kind`.
+    fn value(&mut self, kind: &str) -> Result<Option<OctaveType>, MocktaveError> {
+        // Arrays with more than two dimensions are saved with an `# ndims:` header followed by one
+        // element per line
+        let nd = self.rest().starts_with("# ndims:");
+        if nd
+            && !matches!(kind, "matrix" | "complex matrix" | "bool matrix")
+            && !kind.ends_with("struct")
+            && integer_class(kind, " matrix").is_none()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(match kind {
+            "scalar" => OctaveType::Scalar(parse_float(self.line()?)?),
+            "complex scalar" => {
+                let (re, im) = parse_complex(self.line()?)?;
+                OctaveType::ComplexScalar(re, im)
+            }
+            "bool" => OctaveType::Bool(parse_bool(self.line()?)?),
+            "string" | "sq_string" | "null_string" | "null_sq_string" => {
+                OctaveType::String(self.string()?)
+            }
+            "matrix" if nd => OctaveType::NdArray(self.nd_array(parse_float)?),
+            "complex matrix" if nd => OctaveType::ComplexNdArray(self.nd_array(parse_complex)?),
+            "bool matrix" if nd => OctaveType::BoolNdArray(self.nd_array(parse_bool)?),
+            "matrix" => self.matrix(parse_float, OctaveType::Matrix, OctaveType::NdArray)?,
+            "complex matrix" => self.matrix(
+                parse_complex,
+                OctaveType::ComplexMatrix,
+                OctaveType::ComplexNdArray,
+            )?,
+            "bool matrix" => {
+                self.matrix(parse_bool, OctaveType::BoolMatrix, OctaveType::BoolNdArray)?
+            }
+            "diagonal matrix" => {
+                let rows: usize = parse_number(self.header("rows")?)?;
+                let columns: usize = parse_number(self.header("columns")?)?;
+                // Only the diagonal is saved, so that is all that has to fit
+                self.fits(rows.min(columns), 1)?;
+                if rows.checked_mul(columns).is_none() {
+                    return Err(self.malformed("A matrix is too large"));
+                }
+                let mut matrix = vec![vec![0.0; columns]; rows];
+                for (idx, row) in matrix.iter_mut().enumerate().take(columns) {
+                    row[idx] = parse_float(self.line()?)?;
+                }
+                OctaveType::Matrix(matrix)
+            }
+            "range" => OctaveType::Matrix(vec![self.range()?]),
+            "cell" => OctaveType::CellArray(self.cell()?),
+            "sparse matrix" => {
+                let (rows, cols, triplets) = self.sparse(parse_float)?;
+                OctaveType::SparseMatrix {
+                    rows,
+                    cols,
+                    triplets,
+                }
+            }
+            "sparse complex matrix" => {
+                let (rows, cols, triplets) = self.sparse(parse_complex)?;
+                OctaveType::SparseComplexMatrix {
+                    rows,
+                    cols,
+                    triplets,
+                }
+            }
+            "sparse bool matrix" => {
+                let (rows, cols, triplets) = self.sparse(parse_bool)?;
+                OctaveType::SparseBoolMatrix {
+                    rows,
+                    cols,
+                    triplets,
+                }
+            }
+            "scalar struct" => {
+                self.dimensions()?;
+                OctaveType::Struct(self.fields()?)
+            }
+            "struct" => {
+                let (rows, columns) = self.dimensions()?;
+                self.fits(rows, columns)?;
+                let mut array = vec![vec![Vec::new(); columns]; rows];
+                for (field, value) in self.fields()? {
+                    let OctaveType::CellArray(cell) = value else {
+                        return Err(self.malformed("The fields of a struct array must be cells"));
+                    };
+                    if cell.len() != rows || cell.iter().any(|row| row.len() != columns) {
+                        return Err(self.malformed("A field of a struct array has the wrong size"));
+                    }
+                    for (row, values) in array.iter_mut().zip(cell) {
+                        for (element, value) in row.iter_mut().zip(values) {
+                            element.push((field.clone(), value));
+                        }
+                    }
+                }
+                OctaveType::StructArray(array)
+            }
+            integer => {
+                if let Some(class) = integer_class(integer, " scalar") {
+                    OctaveType::Integer(class, parse_number(self.line()?)?)
+                } else if let Some(class) = integer_class(integer, " matrix") {
+                    let array = self.nd_array(parse_number)?;
+                    match *array.shape() {
+                        // Matrices without rows stay arrays, which keeps their number of columns
+                        [rows, columns] if rows > 0 || columns == 0 => {
+                            let mut matrix = vec![vec![0; columns]; rows];
+                            for (index, element) in array.data().iter().enumerate() {
+                                matrix[index % rows][index / rows] = *element;
+                            }
+                            OctaveType::IntegerMatrix(class, matrix)
+                        }
+                        _ => OctaveType::IntegerNdArray(class, array),
+                    }
+                } else {
+                    return Ok(None);
+                }
+            }
+        }))
+    }
+
+    /// Read a `# rows:`/`# columns:` header followed by one line per row, parsing each element
+    /// with `parse`. A matrix without rows can't say how many columns it has as rows, so it
+    /// becomes a two dimensional array instead.
+    fn matrix<T>(
+        &mut self,
+        parse: impl Fn(&str) -> Result<T, MocktaveError>,
+        as_matrix: fn(Vec<Vec<T>>) -> OctaveType,
+        as_array: fn(NdArray<T>) -> OctaveType,
+    ) -> Result<OctaveType, MocktaveError> {
+        let rows: usize = parse_number(self.header("rows")?)?;
+        let columns: usize = parse_number(self.header("columns")?)?;
+        self.fits(rows, columns)?;
+        if rows == 0 && columns > 0 {
+            let array = NdArray::new(vec![0, columns], Vec::new());
+            return array
+                .map(as_array)
+                .ok_or_else(|| self.malformed("An array has the wrong size"));
+        }
+        let mut matrix = Vec::with_capacity(rows);
+        for _ in 0..rows {
+            let row = self
+                .line()?
+                .split_whitespace()
+                .map(&parse)
+                .collect::<Result<Vec<T>, MocktaveError>>()?;
+            if row.len() != columns {
+                return Err(self.malformed("A matrix row has the wrong number of columns"));
+            }
+            matrix.push(row);
+        }
+        Ok(as_matrix(matrix))
+    }
+
+    /// Read a range, which is saved as its base, limit and increment after a comment saying so.
+    /// Ranges with an increment of zero are saved with their length in place of the limit.
+    fn range(&mut self) -> Result<Vec<f64>, MocktaveError> {
+        let comment = self.line()?;
+        if !comment.starts_with('#') {
+            return Err(self.malformed("Expected the layout of a range"));
+        }
+        let numbers = self
+            .line()?
+            .split_whitespace()
+            .take(3)
+            .map(parse_float)
+            .collect::<Result<Vec<f64>, MocktaveError>>()?;
+        let [base, limit, increment] = numbers[..] else {
+            return Err(self.malformed("A range needs a base, a limit and an increment"));
+        };
+        let length = if comment.contains("length") {
+            limit
+        } else {
+            // Allow for rounding, so `0:0.1:1` still ends at one
+            ((limit - base) / increment + 1e-10).floor() + 1.0
+        };
+        if length.is_nan() || length < 0.0 {
+            return Err(self.malformed("A range has an invalid length"));
+        }
+        Ok((0..length as usize)
+            .map(|idx| base + idx as f64 * increment)
+            .collect())
+    }
+
+    /// Read a sparse matrix, which is saved as one `row column value` line per nonzero element,
+    /// with one-based indices.
+    fn sparse<T>(
+        &mut self,
+        parse: impl Fn(&str) -> Result<T, MocktaveError>,
+    ) -> Result<SparseParts<T>, MocktaveError> {
+        let nnz: usize = parse_number(self.header("nnz")?)?;
+        let rows: usize = parse_number(self.header("rows")?)?;
+        let cols: usize = parse_number(self.header("columns")?)?;
+        self.fits(nnz, 1)?;
+        let mut triplets = Vec::with_capacity(nnz);
+        for _ in 0..nnz {
+            let line = self.line()?;
+            let mut fields = line.split_whitespace();
+            let mut index = || -> Result<usize, MocktaveError> {
+                let field = fields
+                    .next()
+                    .ok_or_else(|| self.malformed("A sparse element needs a row and a column"))?;
+                parse_number::<usize>(field)?
+                    .checked_sub(1)
+                    .ok_or_else(|| self.malformed("Sparse indices start at one"))
+            };
+            let (i, j) = (index()?, index()?);
+            let value = fields
+                .next()
+                .ok_or_else(|| self.malformed("A sparse element needs a value"))?;
+            if i >= rows || j >= cols {
+                return Err(self.malformed("A sparse element is out of bounds"));
+            }
+            triplets.push((i, j, parse(value)?));
+        }
+        Ok((rows, cols, triplets))
+    }
+
+    /// Read a cell array, whose elements are saved as blocks in column-major order.
+    fn cell(&mut self) -> Result<Vec<Vec<OctaveType>>, MocktaveError> {
+        let rows: usize = parse_number(self.header("rows")?)?;
+        let columns: usize = parse_number(self.header("columns")?)?;
+        self.fits(rows, columns)?;
+        let mut cell = vec![vec![OctaveType::Empty; columns]; rows];
+        for index in 0..rows * columns {
+            let (_, value) = self.block()?;
+            cell[index % rows][index / rows] = value.unwrap_or_default();
+        }
+        Ok(cell)
+    }
+
+    /// Read the `# length:` header of a struct and then that many fields, in order.
+    fn fields(&mut self) -> Result<StructFields, MocktaveError> {
+        let length: usize = parse_number(self.header("length")?)?;
+        self.fits(length, 1)?;
+        let mut fields = Vec::with_capacity(length);
+        for _ in 0..length {
+            let (name, value) = self.block()?;
+            fields.push((name, value.unwrap_or_default()));
+        }
+        Ok(fields)
+    }
+
+    /// Read a string, which may span several rows of a character matrix.
+    fn string(&mut self) -> Result<String, MocktaveError> {
+        let elements: usize = parse_number(self.header("elements")?)?;
+        self.fits(elements, 1)?;
+        let mut rows = Vec::with_capacity(elements);
+        for _ in 0..elements {
+            let length = parse_number(self.header("length")?)?;
+            rows.push(self.take(length)?);
+        }
+        Ok(rows.join("\n"))
+    }
+
+    /// Read an array saved with an `# ndims:` header, parsing each element with `parse`.
+    fn nd_array<T>(
+        &mut self,
+        parse: impl Fn(&str) -> Result<T, MocktaveError>,
+    ) -> Result<NdArray<T>, MocktaveError> {
+        let shape = self.shape()?;
+        let count = shape
+            .iter()
+            .try_fold(1_usize, |count, size| count.checked_mul(*size))
+            .ok_or_else(|| self.malformed("An array is too large"))?;
+        self.fits(count, 1)?;
+        let mut data = Vec::with_capacity(count);
+        for _ in 0..count {
+            data.push(parse(self.line()?)?);
+        }
+        NdArray::new(shape, data).ok_or_else(|| self.malformed("An array has the wrong size"))
+    }
+
+    /// Read an `# ndims:` header and the line of dimensions after it.
+    fn shape(&mut self) -> Result<Vec<usize>, MocktaveError> {
+        let ndims: usize = parse_number(self.header("ndims")?)?;
+        let shape = self
+            .line()?
+            .split_whitespace()
+            .map(parse_number)
+            .collect::<Result<Vec<usize>, MocktaveError>>()?;
+        if shape.len() != ndims {
+            return Err(self.malformed("An array has the wrong number of dimensions"));
+        }
+        Ok(shape)
+    }
+
+    /// Read the shape of a value that has to be two dimensional.
+    fn dimensions(&mut self) -> Result<(usize, usize), MocktaveError> {
+        match *self.shape()? {
+            [rows, columns] => Ok((rows, columns)),
+            _ => Err(self.malformed("Only two dimensional values are supported")),
+        }
+    }
+
+    /// Read a `# key: value` line and return the value.
+    fn header(&mut self, key: &str) -> Result<&'a str, MocktaveError> {
+        let line = self.line()?;
+        line.strip_prefix("# ")
+            .and_then(|line| line.strip_prefix(key))
+            .and_then(|line| line.strip_prefix(": "))
+            .ok_or_else(|| self.malformed(&format!("Expected `# {key}:`")))
+    }
+
+    /// Read exactly `length` bytes, followed by the end of the line.
+    fn take(&mut self, length: usize) -> Result<&'a str, MocktaveError> {
+        let start = self.position;
+        self.line_start = start;
+        let taken = self
+            .rest()
+            .get(..length)
+            .ok_or_else(|| self.malformed("A string is shorter than its length"))?;
+        self.position += length;
+        if self.position < self.text.len() && !self.line()?.is_empty() {
+            return Err(self.malformed("A string is longer than its length"));
+        }
+        self.line_start = start;
+        Ok(taken)
+    }
+
+    /// Read the next line, without its newline.
+    fn line(&mut self) -> Result<&'a str, MocktaveError> {
+        if self.position >= self.text.len() {
+            self.line_start = self.text.len();
+            return Err(self.malformed("Unexpected end of output"));
+        }
+        let rest = self.rest();
+        let (line, length) = match rest.find('\n') {
+            Some(end) => (&rest[..end], end + 1),
+            None => (rest, rest.len()),
+        };
+        self.line_start = self.position;
+        self.position += length;
+        Ok(line)
+    }
+
+    fn skip_line(&mut self) {
+        let _ = self.line();
+    }
+
+    fn skip_blank_lines(&mut self) {
+        while self.position < self.text.len() && self.rest().starts_with('\n') {
+            self.position += 1;
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    /// Check that `rows` by `columns` elements could possibly be in the rest of the text, so a
+    /// corrupted header can't make us allocate far more memory than the output itself takes up.
+    fn fits(&self, rows: usize, columns: usize) -> Result<(), MocktaveError> {
+        match rows.checked_mul(columns) {
+            Some(count) if count <= self.text.len() - self.position => Ok(()),
+            _ => Err(self.malformed("A value is larger than the rest of the output")),
+        }
+    }
+
+    /// The line that was read last.
+    fn current_line(&self) -> &'a str {
+        self.text[self.line_start..]
+            .split('\n')
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// The one-based line and column of the byte at `offset`.
+    fn line_column(&self, offset: usize) -> (usize, usize) {
+        let before = &self.text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+        (line, column)
+    }
+
+    /// Report a problem with the line that was read last.
+    fn malformed(&self, message: &str) -> MocktaveError {
+        let (line, column) = self.line_column(self.line_start);
+        MocktaveError::Parse {
+            message: message.to_string(),
+            text: self.current_line().to_string(),
+            line: Some(line),
+            column: Some(column),
+        }
+    }
+
+    /// Point an error that doesn't know where it happened at the text it complains about, on the
+    /// line that was read last.
+    fn locate(&self, error: MocktaveError) -> MocktaveError {
+        match error {
+            MocktaveError::Parse {
+                message,
+                text,
+                line: None,
+                ..
+            } => {
+                let offset = self.current_line().find(text.trim()).unwrap_or_default();
+                let (line, column) = self.line_column(self.line_start + offset);
+                MocktaveError::Parse {
+                    message,
+                    text,
+                    line: Some(line),
+                    column: Some(column),
+                }
+            }
+            error => error,
+        }
+    }
+}
+
+/// Work out the integer class of a type like `int32 scalar`, given its `suffix`.
+fn integer_class(kind: &str, suffix: &str) -> Option<IntegerClass> {
+    kind.strip_suffix(suffix)?.parse().ok()
+}
+
+/// Parse a number out of the text, complaining with the offending text if it isn't one.
+fn parse_number<T>(text: &str) -> Result<T, MocktaveError>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Display,
+{
+    T::from_str(text.trim()).map_err(|error| MocktaveError::Parse {
+        message: error.to_string(),
+        text: text.trim().to_string(),
+        line: None,
+        column: None,
+    })
+}
+
+/// Parse a float, including `NA`, which Octave uses for missing values.
+fn parse_float(text: &str) -> Result<f64, MocktaveError> {
+    match text.trim() {
+        "NA" => Ok(f64::NAN),
+        text => parse_number(text),
+    }
+}
+
+/// Parse a logical value, which is saved as a number.
+fn parse_bool(text: &str) -> Result<bool, MocktaveError> {
+    Ok(parse_float(text)? != 0.0)
+}
+
+/// Parse a complex number written as `(re,im)`.
+fn parse_complex(text: &str) -> Result<(f64, f64), MocktaveError> {
+    let (re, im) = text
+        .trim()
+        .strip_prefix('(')
+        .and_then(|text| text.strip_suffix(')'))
+        .and_then(|text| text.split_once(','))
+        .ok_or_else(|| MocktaveError::Parse {
+            message: "A complex number is written as `(re,im)`".to_string(),
+            text: text.trim().to_string(),
+            line: None,
+            column: None,
+        })?;
+    Ok((parse_float(re)?, parse_float(im)?))
+}
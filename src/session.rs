@@ -0,0 +1,376 @@
+//! A long-lived Octave process that commands are piped into one at a time, wherever that process
+//! happens to run.
+//!
+//! Every call to [`Session::eval`] writes a small wrapper around the user's script to the
+//! process' stdin. The wrapper prints unique sentinel lines around the script output and the
+//! workspace dump, which is how we know where one command ends and the next one begins.
+//!
+//! An `eval` can be dropped halfway, for example by a timeout around an async interpreter. The
+//! sentinels are different for every command, so whatever a dropped command still prints is
+//! skipped by the next one, and the part of a command that wasn't written yet is written first.
+
+use std::pin::Pin;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures_util::{Stream, StreamExt};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{InterpreterResults, MocktaveError, OctaveError, OctaveWarning, StackFrame};
+
+/// The arguments Octave is started with, so that it reads commands from stdin one at a time.
+pub(crate) const OCTAVE_ARGS: [&str; 4] =
+    ["--no-gui", "--quiet", "--interactive", "--no-line-editing"];
+
+/// Commands run once when the session starts, so prompts and paging don't pollute the output.
+const PRELUDE: &str = "PS1(\"\"); PS2(\"\"); more off;\n";
+
+/// The output of a single command sent to the session.
+pub(crate) struct Exchange {
+    /// Whatever the script printed to stdout.
+    pub(crate) stdout: String,
+    /// Whatever the script printed to stderr.
+    pub(crate) stderr: String,
+    /// The workspace after the script ran, in Octave's text save format.
+    pub(crate) workspace: String,
+    /// The error raised by the script, if any.
+    pub(crate) error: Option<OctaveError>,
+    /// The warnings raised by the script, in the order they were raised.
+    pub(crate) warnings: Vec<OctaveWarning>,
+}
+
+impl Exchange {
+    /// Parse the workspace and collect everything else that was reported alongside it.
+    pub(crate) fn into_results(self) -> Result<InterpreterResults, MocktaveError> {
+        let mut results = InterpreterResults::try_from(self.workspace)?;
+        results.stdout = self.stdout;
+        results.stderr = self.stderr;
+        results.error = self.error;
+        results.warnings = self.warnings;
+        Ok(results)
+    }
+}
+
+/// A piece of whatever the Octave process printed.
+pub(crate) enum Chunk {
+    /// Bytes written to stdout.
+    Stdout(Vec<u8>),
+    /// Bytes written to stderr.
+    Stderr(Vec<u8>),
+}
+
+/// The stdin of an Octave process.
+pub(crate) type Input = Pin<Box<dyn AsyncWrite + Send>>;
+
+/// The stdout and stderr of an Octave process, interleaved.
+pub(crate) type Output = Pin<Box<dyn Stream<Item = Result<Chunk, MocktaveError>> + Send>>;
+
+/// An Octave process with attached stdin and stdout.
+pub(crate) struct Session {
+    input: Input,
+    output: Output,
+    token: String,
+    /// How many commands were sent, which tells the sentinels of one command from the next.
+    commands: u64,
+    /// Whatever a dropped `eval` didn't get to write.
+    unsent: Vec<u8>,
+    /// Identifiers of the warnings Octave raises as errors, since that setting outlives the
+    /// command that made it.
+    promoted: Vec<String>,
+}
+
+impl Session {
+    /// Take over an Octave process that was started with [`OCTAVE_ARGS`].
+    pub(crate) async fn new(input: Input, output: Output) -> Result<Self, MocktaveError> {
+        let mut session = Session {
+            input,
+            output,
+            token: token(),
+            commands: 0,
+            unsent: Vec::new(),
+            promoted: Vec::new(),
+        };
+        session.write(PRELUDE).await?;
+        Ok(session)
+    }
+
+    /// Run `script` in the session and wait until its output and the workspace dump are back.
+    /// Warnings with an identifier in `promoted` are raised as errors instead, and only those:
+    /// warnings promoted for earlier scripts are turned back into warnings.
+    pub(crate) async fn eval(
+        &mut self,
+        script: &str,
+        promoted: &[String],
+    ) -> Result<Exchange, MocktaveError> {
+        self.commands += 1;
+        let command = self.command(script, promoted);
+        self.promoted = promoted.to_vec();
+        self.write(&command).await?;
+
+        // Both streams start and end with a marker, so nothing printed to stderr is left behind for
+        // the next command even when stderr and stdout arrive through separate pipes, and anything
+        // left behind by a dropped command comes before the start
+        let end = self.marker("end") + "\n";
+        let mut stdout = Scan::default();
+        let mut stderr = Scan::default();
+        while stdout.end.is_none() || stderr.end.is_none() {
+            match self.output.next().await {
+                Some(Ok(Chunk::Stdout(message))) => stdout.push(&message, &end),
+                Some(Ok(Chunk::Stderr(message))) => stderr.push(&message, &end),
+                Some(Err(error)) => return Err(error),
+                None => {
+                    return Err(MocktaveError::BackendUnavailable(
+                        "The Octave process exited unexpectedly.".to_string(),
+                    ))
+                }
+            }
+        }
+
+        let stdout = String::from_utf8_lossy(stdout.finished());
+        let (_, body) = split(&stdout, &(self.marker("begin") + "\n"))?;
+        let (output, dump) = split(body, &("\n".to_string() + &self.marker("dump") + "\n"))?;
+        let (output, last_warning) =
+            split(output, &("\n".to_string() + &self.marker("warning") + "\n"))?;
+        let (user, error) =
+            match output.split_once(&("\n".to_string() + &self.marker("error") + "\n")) {
+                Some((user, error)) => (user, Some(parse_error(error)?)),
+                None => (output, None),
+            };
+
+        let stderr = String::from_utf8_lossy(stderr.finished());
+        let (_, stderr) = split(&stderr, &(self.marker("begin") + "\n"))?;
+        let stderr = stderr.to_string();
+        Ok(Exchange {
+            stdout: user.to_string(),
+            warnings: parse_warnings(&stderr, last_warning),
+            stderr,
+            workspace: dump.to_string(),
+            error,
+        })
+    }
+
+    /// Wrap `script` so that it runs as a single unit and reports the resulting workspace.
+    fn command(&self, script: &str, promoted: &[String]) -> String {
+        // Warnings promoted for an earlier command stay errors until they are turned back on
+        let demotions = self
+            .promoted
+            .iter()
+            .filter(|identifier| !promoted.contains(identifier))
+            .map(|identifier| format!("warning(\"on\", \"{}\");\n", escape(identifier)))
+            .collect::<String>();
+        let promotions = promoted
+            .iter()
+            .map(|identifier| format!("warning(\"error\", \"{}\");\n", escape(identifier)))
+            .collect::<String>();
+        // Octave only remembers the most recent warning, so forget the one from the last command
+        [
+            demotions + &promotions + "lastwarn(\"\", \"\");",
+            format!("__mocktave_script__ = \"{}\";", escape(script)),
+            format!("printf(\"%s\\n\", \"{}\");", self.marker("begin")),
+            format!("fprintf(stderr, \"%s\\n\", \"{}\");", self.marker("begin")),
+            "try".to_string(),
+            "eval(__mocktave_script__);".to_string(),
+            "catch __mocktave_error__".to_string(),
+            "fprintf(stderr, \"error: %s\\n\", __mocktave_error__.message);".to_string(),
+            format!("printf(\"\\n%s\\n\", \"{}\");", self.marker("error")),
+            "printf(\"%s\\n%d\\n\", __mocktave_error__.identifier, numel(__mocktave_error__.stack));".to_string(),
+            "if numel(__mocktave_error__.stack) > 0".to_string(),
+            "__mocktave_stack__ = __mocktave_error__.stack;".to_string(),
+            "__mocktave_stack__ = [{__mocktave_stack__.name}; {__mocktave_stack__.line}; {__mocktave_stack__.column}];".to_string(),
+            "printf(\"%s\\t%d\\t%d\\n\", __mocktave_stack__{:});".to_string(),
+            "end".to_string(),
+            "printf(\"%s\", __mocktave_error__.message);".to_string(),
+            "end_try_catch".to_string(),
+            "[__mocktave_warning__, __mocktave_warning_id__] = lastwarn();".to_string(),
+            format!("printf(\"\\n%s\\n\", \"{}\");", self.marker("warning")),
+            "printf(\"%s\\n%s\", __mocktave_warning_id__, __mocktave_warning__);".to_string(),
+            "clear __mocktave_script__ __mocktave_error__ __mocktave_stack__ __mocktave_warning__ __mocktave_warning_id__".to_string(),
+            format!("printf(\"\\n%s\\n\", \"{}\");", self.marker("dump")),
+            "save(\"-text\", \"-\", \"*\");".to_string(),
+            format!("fprintf(stderr, \"%s\\n\", \"{}\");", self.marker("end")),
+            "fflush(stderr);".to_string(),
+            format!("printf(\"%s\\n\", \"{}\");", self.marker("end")),
+            "fflush(stdout);\n".to_string(),
+        ]
+        .join("\n")
+    }
+
+    /// A line that no script is going to print by accident, and that no other command prints.
+    fn marker(&self, name: &str) -> String {
+        format!("__mocktave_{}_{}_{name}__", self.token, self.commands)
+    }
+
+    /// Write `text` after anything a dropped `eval` left unwritten. Only the part that was
+    /// actually written is taken off, so this can be dropped at any point too.
+    async fn write(&mut self, text: &str) -> Result<(), MocktaveError> {
+        self.unsent.extend_from_slice(text.as_bytes());
+        while !self.unsent.is_empty() {
+            let written = self
+                .input
+                .write(&self.unsent)
+                .await
+                .map_err(|error| MocktaveError::BackendUnavailable(error.to_string()))?;
+            if written == 0 {
+                return Err(MocktaveError::BackendUnavailable(
+                    "The Octave process stopped reading commands.".to_string(),
+                ));
+            }
+            self.unsent.drain(..written);
+        }
+        self.input
+            .flush()
+            .await
+            .map_err(|error| MocktaveError::BackendUnavailable(error.to_string()))
+    }
+}
+
+/// Everything read from one of the process' streams, up to the marker that ends a command.
+#[derive(Default)]
+struct Scan {
+    bytes: Vec<u8>,
+    /// Where the end marker starts, once it has arrived.
+    end: Option<usize>,
+    /// How much of `bytes` is known not to contain the start of the end marker.
+    searched: usize,
+}
+
+impl Scan {
+    fn push(&mut self, message: &[u8], end: &str) {
+        self.bytes.extend_from_slice(message);
+        if self.end.is_none() {
+            self.end = find(&self.bytes, end.as_bytes(), self.searched);
+            self.searched = self.bytes.len().saturating_sub(end.len());
+        }
+    }
+
+    /// The bytes before the end marker.
+    fn finished(&self) -> &[u8] {
+        &self.bytes[..self.end.unwrap_or(self.bytes.len())]
+    }
+}
+
+/// Read the identifier, stack and message printed when the script raised an error.
+fn parse_error(text: &str) -> Result<OctaveError, MocktaveError> {
+    let malformed = || MocktaveError::Parse {
+        message: "Malformed error report".to_string(),
+        text: text.to_string(),
+        line: None,
+        column: None,
+    };
+
+    let mut rest = text;
+    let mut line = || -> Result<&str, MocktaveError> {
+        let (line, remainder) = rest.split_once('\n').ok_or_else(malformed)?;
+        rest = remainder;
+        Ok(line)
+    };
+
+    let identifier = line()?.to_string();
+    let frames = line()?.parse::<usize>().map_err(|_| malformed())?;
+    let mut stack = Vec::with_capacity(frames);
+    for _ in 0..frames {
+        let mut fields = line()?.split('\t');
+        let name = fields.next().ok_or_else(malformed)?.to_string();
+        let mut number = || -> Result<usize, MocktaveError> {
+            let field = fields.next().ok_or_else(malformed)?;
+            Ok(field.parse::<i64>().map_err(|_| malformed())?.max(0) as usize)
+        };
+        stack.push(StackFrame {
+            name,
+            line: number()?,
+            column: number()?,
+        });
+    }
+
+    Ok(OctaveError {
+        message: rest.to_string(),
+        identifier: if identifier.is_empty() {
+            None
+        } else {
+            Some(identifier)
+        },
+        stack,
+    })
+}
+
+/// Collect the warnings Octave printed to stderr, along with the backtraces it prints after
+/// warnings raised inside functions. `last` holds the identifier and message of the most recent
+/// warning as reported by `lastwarn`, which is the only way to learn a warning's identifier.
+fn parse_warnings(stderr: &str, last: &str) -> Vec<OctaveWarning> {
+    let mut warnings = Vec::<OctaveWarning>::new();
+    let mut lines = stderr.lines().peekable();
+    while let Some(line) = lines.next() {
+        if line == "warning: called from" {
+            while let Some(frame) = lines.peek().and_then(|line| parse_frame(line)) {
+                if let Some(warning) = warnings.last_mut() {
+                    warning.stack.push(frame);
+                }
+                lines.next();
+            }
+        } else if let Some(message) = line.strip_prefix("warning: ") {
+            warnings.push(OctaveWarning {
+                message: message.to_string(),
+                ..Default::default()
+            });
+        }
+    }
+
+    if let Some((identifier, message)) = last.split_once('\n') {
+        if !identifier.is_empty() {
+            if let Some(warning) = warnings
+                .iter_mut()
+                .rev()
+                .find(|warning| warning.message == message)
+            {
+                warning.identifier = Some(identifier.to_string());
+            }
+        }
+    }
+    warnings
+}
+
+/// Read a backtrace line like `    f at line 3 column 5`.
+fn parse_frame(line: &str) -> Option<StackFrame> {
+    let line = line.strip_prefix("    ")?;
+    let (name, position) = line.rsplit_once(" at line ")?;
+    let (line, column) = position.split_once(" column ")?;
+    Some(StackFrame {
+        name: name.to_string(),
+        line: line.parse().ok()?,
+        column: column.parse().ok()?,
+    })
+}
+
+/// Split `text` on `marker`, complaining if the marker is missing.
+fn split<'a>(text: &'a str, marker: &str) -> Result<(&'a str, &'a str), MocktaveError> {
+    text.split_once(marker).ok_or_else(|| MocktaveError::Parse {
+        message: format!("Could not find `{}`", marker.trim()),
+        text: text.to_string(),
+        line: None,
+        column: None,
+    })
+}
+
+/// Escape `text` so it can be placed inside a double-quoted Octave string.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Make a token unique enough to tell our sentinels apart from user output.
+pub(crate) fn token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    format!("{:x}{:x}", std::process::id(), nanos)
+}
+
+/// Find `needle` in `haystack`, ignoring the first `from` bytes.
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack[from..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| position + from)
+}
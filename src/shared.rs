@@ -0,0 +1,121 @@
+//! The interpreters behind the free functions, which are shared by the whole process.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::OnceLock;
+
+use crate::pool::{InterpreterPool, PooledInterpreter};
+use crate::{Interpreter, MocktaveError};
+
+/// The environment variable that gives every call to a free function an interpreter of its own
+/// when set to anything but `0`.
+const ISOLATE: &str = "MOCKTAVE_ISOLATE";
+
+/// The label on the containers of the shared pool, whose value is the id of the process that
+/// started them.
+#[cfg(feature = "docker")]
+const LABEL: &str = "mocktave.shared";
+
+/// The pool the free functions borrow interpreters from, made the first time one is needed.
+static POOL: OnceLock<InterpreterPool> = OnceLock::new();
+
+/// An interpreter for a single call to a free function.
+pub(crate) enum Lease {
+    /// Borrowed from the shared pool.
+    Shared(PooledInterpreter<'static>),
+    /// Started for this call alone.
+    Isolated(Interpreter),
+}
+
+impl Deref for Lease {
+    type Target = Interpreter;
+
+    fn deref(&self) -> &Interpreter {
+        match self {
+            Lease::Shared(interp) => interp,
+            Lease::Isolated(interp) => interp,
+        }
+    }
+}
+
+impl DerefMut for Lease {
+    fn deref_mut(&mut self) -> &mut Interpreter {
+        match self {
+            Lease::Shared(interp) => interp,
+            Lease::Isolated(interp) => interp,
+        }
+    }
+}
+
+/// Get an interpreter for a call to a free function, borrowing one from the shared pool unless
+/// `MOCKTAVE_ISOLATE` asks for a new one.
+pub(crate) fn lease() -> Result<Lease, MocktaveError> {
+    if isolated() {
+        return Ok(Lease::Isolated(Interpreter::try_new()?));
+    }
+    Ok(Lease::Shared(pool().try_lease()?))
+}
+
+/// Stop the shared interpreters that aren't in use. Calls made afterwards start new ones.
+pub(crate) fn shutdown() {
+    if let Some(pool) = POOL.get() {
+        pool.close_idle();
+    }
+}
+
+/// Whether `MOCKTAVE_ISOLATE` is set.
+fn isolated() -> bool {
+    std::env::var(ISOLATE).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+/// The shared pool, which can start one interpreter per thread the machine can run at once.
+fn pool() -> &'static InterpreterPool {
+    POOL.get_or_init(|| {
+        let size = std::thread::available_parallelism().map_or(1, usize::from);
+        #[cfg(feature = "docker")]
+        {
+            remove_at_exit();
+            Interpreter::builder()
+                .label(LABEL, &std::process::id().to_string())
+                .build_pool(size)
+        }
+        #[cfg(not(feature = "docker"))]
+        InterpreterPool::new(size)
+    })
+}
+
+/// Remove the containers of the shared pool when the process exits, since statics are never
+/// dropped. The runtime the containers were started from may be gone by then, so this leaves the
+/// work to the `docker` command line client, and quietly does nothing if it isn't installed.
+#[cfg(feature = "docker")]
+fn remove_at_exit() {
+    use std::process::{Command, Stdio};
+
+    extern "C" fn remove() {
+        let filter = format!("label={LABEL}={}", std::process::id());
+        let Ok(listed) = Command::new("docker")
+            .args(["ps", "--all", "--quiet", "--filter", &filter])
+            .stderr(Stdio::null())
+            .output()
+        else {
+            return;
+        };
+        let listed = String::from_utf8_lossy(&listed.stdout);
+        let ids = listed.split_whitespace().collect::<Vec<_>>();
+        if !ids.is_empty() {
+            let _ = Command::new("docker")
+                .args(["rm", "--force"])
+                .args(ids)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+    }
+    extern "C" {
+        fn atexit(callback: extern "C" fn()) -> std::ffi::c_int;
+    }
+    // SAFETY: `atexit` is part of the C standard library that every Rust program links, and
+    // `remove` only starts processes, which doesn't need anything that is torn down at exit.
+    unsafe {
+        atexit(remove);
+    }
+}
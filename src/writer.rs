@@ -0,0 +1,220 @@
+//! A writer for Octave's text save format, the inverse of the reader in `reader`.
+//!
+//! The layout follows what `save -text` produces, so anything written here can be read back by
+//! Octave's `load` as well as by [`InterpreterResults`](crate::InterpreterResults).
+
+use std::fmt::Write;
+
+use crate::literal::float;
+use crate::{MocktaveError, NdArray, OctaveType, StructFields, Triplets};
+
+/// Write `value` as a complete `# name:`/`# type:` block, including the blank lines after it.
+/// Fails for values Octave can't hold, like struct arrays whose elements have different fields.
+pub(crate) fn block(name: &str, value: &OctaveType) -> Result<String, MocktaveError> {
+    let mut text = String::new();
+    write_block(&mut text, name, value)?;
+    Ok(text)
+}
+
+fn write_block(text: &mut String, name: &str, value: &OctaveType) -> Result<(), MocktaveError> {
+    let _ = writeln!(text, "# name: {name}");
+    let _ = writeln!(text, "# type: {}", kind(value));
+    write_value(text, value)?;
+    text.push_str("\n\n");
+    Ok(())
+}
+
+/// The name Octave uses for the type of `value`.
+fn kind(value: &OctaveType) -> String {
+    match value {
+        OctaveType::Scalar(_) => "scalar".to_string(),
+        OctaveType::ComplexScalar(..) => "complex scalar".to_string(),
+        OctaveType::Matrix(_) | OctaveType::NdArray(_) | OctaveType::Empty => "matrix".to_string(),
+        OctaveType::ComplexMatrix(_) | OctaveType::ComplexNdArray(_) => {
+            "complex matrix".to_string()
+        }
+        OctaveType::Integer(class, _) => format!("{class} scalar"),
+        OctaveType::IntegerMatrix(class, _) | OctaveType::IntegerNdArray(class, _) => {
+            format!("{class} matrix")
+        }
+        OctaveType::SparseMatrix { .. } => "sparse matrix".to_string(),
+        OctaveType::SparseComplexMatrix { .. } => "sparse complex matrix".to_string(),
+        OctaveType::SparseBoolMatrix { .. } => "sparse bool matrix".to_string(),
+        OctaveType::String(_) | OctaveType::Error(_) => "string".to_string(),
+        OctaveType::Bool(_) => "bool".to_string(),
+        OctaveType::BoolMatrix(_) | OctaveType::BoolNdArray(_) => "bool matrix".to_string(),
+        OctaveType::CellArray(_) => "cell".to_string(),
+        OctaveType::Struct(_) => "scalar struct".to_string(),
+        OctaveType::StructArray(_) => "struct".to_string(),
+    }
+}
+
+/// Write everything that comes after the `# type:` line.
+fn write_value(text: &mut String, value: &OctaveType) -> Result<(), MocktaveError> {
+    match value {
+        OctaveType::Scalar(scalar) => {
+            let _ = writeln!(text, "{}", float(*scalar));
+        }
+        OctaveType::ComplexScalar(re, im) => {
+            let _ = writeln!(text, "{}", complex(&(*re, *im)));
+        }
+        OctaveType::Matrix(matrix) => matrix_rows(text, matrix, |el| float(*el)),
+        OctaveType::ComplexMatrix(matrix) => matrix_rows(text, matrix, complex),
+        OctaveType::Integer(_, integer) => {
+            let _ = writeln!(text, "{integer}");
+        }
+        // Integer matrices are always saved with `# ndims:`, even when they are two dimensional
+        OctaveType::IntegerMatrix(_, matrix) => {
+            nd_array(text, &NdArray::from(matrix.clone()), i128::to_string)
+        }
+        OctaveType::NdArray(array) => nd_array(text, array, |el| float(*el)),
+        OctaveType::ComplexNdArray(array) => nd_array(text, array, complex),
+        OctaveType::BoolNdArray(array) => nd_array(text, array, |el| u8::from(*el).to_string()),
+        OctaveType::IntegerNdArray(_, array) => nd_array(text, array, i128::to_string),
+        OctaveType::SparseMatrix {
+            rows,
+            cols,
+            triplets,
+        } => sparse(text, *rows, *cols, triplets, |el| float(*el)),
+        OctaveType::SparseComplexMatrix {
+            rows,
+            cols,
+            triplets,
+        } => sparse(text, *rows, *cols, triplets, complex),
+        OctaveType::SparseBoolMatrix {
+            rows,
+            cols,
+            triplets,
+        } => sparse(text, *rows, *cols, triplets, |el| u8::from(*el).to_string()),
+        // An error is kept as its message, since Octave has no value to hold it. Line breaks are
+        // kept inside a single row, because the rows of a character matrix must all be as long
+        OctaveType::String(string) | OctaveType::Error(string) => {
+            if string.is_empty() {
+                text.push_str("# elements: 0\n");
+            } else {
+                let _ = writeln!(text, "# elements: 1\n# length: {}\n{string}", string.len());
+            }
+        }
+        OctaveType::Bool(boolean) => {
+            let _ = writeln!(text, "{}", u8::from(*boolean));
+        }
+        OctaveType::BoolMatrix(matrix) => matrix_rows(text, matrix, |el| u8::from(*el).to_string()),
+        OctaveType::CellArray(cell) => {
+            let (rows, columns) = size(cell);
+            let _ = writeln!(text, "# rows: {rows}");
+            let _ = writeln!(text, "# columns: {columns}");
+            for column in 0..columns {
+                for row in cell {
+                    write_block(text, "<cell-element>", &row[column])?;
+                }
+                text.push('\n');
+            }
+        }
+        OctaveType::Struct(fields) => {
+            text.push_str("# ndims: 2\n 1 1\n");
+            struct_fields(text, fields)?;
+        }
+        OctaveType::StructArray(array) => {
+            let (rows, columns) = size(array);
+            let _ = writeln!(text, "# ndims: 2\n {rows} {columns}");
+            struct_fields(text, &columns_of(array)?)?;
+        }
+        OctaveType::Empty => text.push_str("# rows: 0\n# columns: 0\n"),
+    }
+    Ok(())
+}
+
+/// Turn a struct array into one cell per field, which is how Octave stores struct arrays. Fields
+/// are looked up by name, so elements may list them in any order, but every element must have
+/// the same fields.
+pub(crate) fn columns_of(array: &[Vec<StructFields>]) -> Result<StructFields, MocktaveError> {
+    let names: Vec<String> = array
+        .iter()
+        .flatten()
+        .next()
+        .map(|fields| fields.iter().map(|(name, _)| name.clone()).collect())
+        .unwrap_or_default();
+    let field = |fields: &StructFields, name: &str| {
+        let value = fields.iter().find(|(field, _)| field == name);
+        match value {
+            Some((_, value)) if fields.len() == names.len() => Ok(value.clone()),
+            _ => Err(MocktaveError::InvalidValue(format!(
+                "The elements of a struct array must all have the fields {names:?}"
+            ))),
+        }
+    };
+    names
+        .iter()
+        .map(|name| {
+            let cell = array
+                .iter()
+                .map(|row| row.iter().map(|fields| field(fields, name)).collect())
+                .collect::<Result<Vec<Vec<OctaveType>>, MocktaveError>>()?;
+            Ok((name.clone(), OctaveType::CellArray(cell)))
+        })
+        .collect()
+}
+
+/// Write a `# length:` header and then each field as its own block.
+fn struct_fields(text: &mut String, fields: &StructFields) -> Result<(), MocktaveError> {
+    let _ = writeln!(text, "# length: {}", fields.len());
+    for (name, value) in fields {
+        write_block(text, name, value)?;
+    }
+    Ok(())
+}
+
+/// Write a `# rows:`/`# columns:` header followed by one line per row.
+fn matrix_rows<T>(text: &mut String, matrix: &[Vec<T>], element: impl Fn(&T) -> String) {
+    let (rows, columns) = size(matrix);
+    let _ = writeln!(text, "# rows: {rows}");
+    let _ = writeln!(text, "# columns: {columns}");
+    for row in matrix {
+        for el in row {
+            let _ = write!(text, " {}", element(el));
+        }
+        text.push('\n');
+    }
+}
+
+/// Write an `# ndims:` header and the shape, followed by one element per line in column-major
+/// order.
+fn nd_array<T>(text: &mut String, array: &NdArray<T>, element: impl Fn(&T) -> String) {
+    let _ = writeln!(text, "# ndims: {}", array.shape().len());
+    for size in array.shape() {
+        let _ = write!(text, " {size}");
+    }
+    text.push('\n');
+    for el in array.data() {
+        let _ = writeln!(text, " {}", element(el));
+    }
+}
+
+/// Write a sparse matrix as one `row column value` line per element, with one-based indices and
+/// in column-major order like Octave expects.
+fn sparse<T>(
+    text: &mut String,
+    rows: usize,
+    cols: usize,
+    triplets: &Triplets<T>,
+    element: impl Fn(&T) -> String,
+) {
+    let mut order = triplets.iter().collect::<Vec<_>>();
+    order.sort_by_key(|(i, j, _)| (*j, *i));
+    let _ = writeln!(text, "# nnz: {}", triplets.len());
+    let _ = writeln!(text, "# rows: {rows}");
+    let _ = writeln!(text, "# columns: {cols}");
+    for (i, j, value) in order {
+        let _ = writeln!(text, "{} {} {}", i + 1, j + 1, element(value));
+    }
+}
+
+/// The number of rows and columns of a matrix given as rows.
+fn size<T>(matrix: &[Vec<T>]) -> (usize, usize) {
+    (matrix.len(), matrix.first().map(Vec::len).unwrap_or(0))
+}
+
+/// Write a complex number as `(re,im)`.
+fn complex((re, im): &(f64, f64)) -> String {
+    format!("({},{})", float(*re), float(*im))
+}
@@ -0,0 +1,165 @@
+//! Records scripts run by a fake backend and replays them, checking that everything a script
+//! reports survives the trip through a recording.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use mocktave::{
+    FakeBackend, Interpreter, InterpreterResults, MocktaveError, OctaveError, OctaveType,
+    OctaveWarning, RecordMode, StackFrame,
+};
+
+/// An empty directory for the recordings of one test.
+fn fixtures(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mocktave-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// An interpreter that records into `dir`, counting the scripts that actually ran in `runs`.
+fn recording(dir: &PathBuf, mode: RecordMode, runs: &Arc<AtomicUsize>) -> Interpreter {
+    let runs = runs.clone();
+    Interpreter::builder()
+        .cassette(dir)
+        .record_mode(mode)
+        .backend(move || {
+            let runs = runs.clone();
+            FakeBackend::new().on(move |script, workspace| {
+                runs.fetch_add(1, Ordering::SeqCst);
+                let x = match workspace.get("x") {
+                    Some(OctaveType::Scalar(x)) => *x,
+                    _ => 0.0,
+                };
+                match script {
+                    "y = 2 * x" => Some(InterpreterResults::default().with_variable("y", 2.0 * x)),
+                    "report" => Some(
+                        InterpreterResults::default()
+                            .with_variable("s", "multi\nline")
+                            .with_stdout("printed\n")
+                            .with_stderr("warning: careful\nerror: boom\n")
+                            .with_warning(OctaveWarning {
+                                message: "careful".to_string(),
+                                identifier: Some("mocktave:careful".to_string()),
+                                stack: vec![StackFrame {
+                                    name: "f".to_string(),
+                                    line: 3,
+                                    column: 5,
+                                }],
+                            })
+                            .with_error(OctaveError {
+                                message: "boom".to_string(),
+                                identifier: None,
+                                stack: Vec::new(),
+                            }),
+                    ),
+                    _ => None,
+                }
+            })
+        })
+        .build()
+}
+
+#[test]
+fn recordings_are_replayed_without_the_backend() {
+    let dir = fixtures("replayed");
+    let runs = Arc::new(AtomicUsize::new(0));
+
+    let mut interp = recording(&dir, RecordMode::New, &runs);
+    let recorded = interp.eval("report");
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    let mut interp = recording(&dir, RecordMode::ReplayOnly, &runs);
+    let replayed = interp.eval("report");
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    assert_eq!(replayed.workspace(), recorded.workspace());
+    assert_eq!(replayed.get_string("s").unwrap(), "multi\nline");
+    assert_eq!(replayed.stdout(), "printed\n");
+    assert_eq!(replayed.stderr(), recorded.stderr());
+    assert_eq!(replayed.error(), recorded.error());
+    assert_eq!(replayed.warnings(), recorded.warnings());
+}
+
+#[test]
+fn inputs_are_part_of_the_recording() {
+    let dir = fixtures("inputs");
+    let runs = Arc::new(AtomicUsize::new(0));
+
+    let mut interp = recording(&dir, RecordMode::New, &runs);
+    interp.set("x", 1.0);
+    assert_eq!(interp.eval("y = 2 * x").get_scalar("y"), Some(2.0));
+    interp.set("x", 2.0);
+    assert_eq!(interp.eval("y = 2 * x").get_scalar("y"), Some(4.0));
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+    // The second recording was made with `y` already in the workspace, so it is only found again
+    // that way
+    let mut interp = recording(&dir, RecordMode::ReplayOnly, &runs);
+    interp.set("x", 2.0);
+    assert!(interp.try_eval("y = 2 * x").is_err());
+    interp.set("x", 1.0);
+    assert_eq!(interp.eval("y = 2 * x").get_scalar("y"), Some(2.0));
+    interp.set("x", 2.0);
+    assert_eq!(interp.eval("y = 2 * x").get_scalar("y"), Some(4.0));
+    interp.set("x", 3.0);
+    let err = interp.try_eval("y = 2 * x").unwrap_err();
+    assert_eq!(err, MocktaveError::Unmatched("y = 2 * x".to_string()));
+}
+
+#[test]
+fn recording_after_replaying_catches_up_on_the_workspace() {
+    let dir = fixtures("catch-up");
+    let runs = Arc::new(AtomicUsize::new(0));
+
+    let mut interp = recording(&dir, RecordMode::New, &runs);
+    interp.set("x", 5.0);
+    interp.eval("y = 2 * x");
+
+    // The first script is replayed, so the recorder only hears about `x` when it has to record
+    let mut interp = recording(&dir, RecordMode::New, &runs);
+    interp.set("x", 5.0);
+    interp.eval("y = 2 * x");
+    let before = runs.load(Ordering::SeqCst);
+    interp.set("x", 7.0);
+    assert_eq!(interp.eval("y = 2 * x").get_scalar("y"), Some(14.0));
+    assert!(runs.load(Ordering::SeqCst) > before);
+}
+
+#[test]
+fn recording_everything_runs_every_script() {
+    let dir = fixtures("all");
+    let runs = Arc::new(AtomicUsize::new(0));
+
+    recording(&dir, RecordMode::New, &runs).eval("report");
+    recording(&dir, RecordMode::New, &runs).eval("report");
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    recording(&dir, RecordMode::All, &runs).eval("report");
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn calls_are_recorded_too() {
+    let dir = fixtures("calls");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let build = |mode| {
+        let calls = calls.clone();
+        Interpreter::builder()
+            .cassette(&dir)
+            .record_mode(mode)
+            .backend(move || {
+                let calls = calls.clone();
+                FakeBackend::new().on_call("norm", move |_| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    vec![OctaveType::Scalar(5.0)]
+                })
+            })
+            .build()
+    };
+
+    let norm: f64 = build(RecordMode::New).call("norm", &[vec![3.0, 4.0].into()]);
+    assert_eq!(norm, 5.0);
+    let mut interp = build(RecordMode::ReplayOnly);
+    let norm: f64 = interp.call("norm", &[vec![3.0, 4.0].into()]);
+    assert_eq!(norm, 5.0);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,191 @@
+//! Drives both interpreters through a fake backend, which checks everything between the public API
+//! and the backend without needing Octave.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use mocktave::{
+    AsyncInterpreter, Backend, FakeBackend, Interpreter, InterpreterResults, MocktaveError,
+    OctaveType, OctaveWarning,
+};
+
+/// Passes everything on to a fake, keeping the scripts it was sent.
+struct Recorder {
+    fake: FakeBackend,
+    scripts: Arc<Mutex<Vec<String>>>,
+}
+
+impl Backend for Recorder {
+    fn eval<'a>(
+        &'a mut self,
+        script: &'a str,
+        promoted: &'a [String],
+    ) -> BoxFuture<'a, Result<InterpreterResults, MocktaveError>> {
+        self.scripts.lock().unwrap().push(script.to_string());
+        self.fake.eval(script, promoted)
+    }
+
+    fn load<'a>(
+        &'a mut self,
+        variables: &'a [(String, OctaveType)],
+    ) -> BoxFuture<'a, Result<(), MocktaveError>> {
+        self.fake.load(variables)
+    }
+
+    fn restart(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>> {
+        self.fake.restart()
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), MocktaveError>> {
+        self.fake.close()
+    }
+}
+
+fn singular() -> InterpreterResults {
+    InterpreterResults::default()
+        .with_variable("x", 1.0)
+        .with_warning(OctaveWarning {
+            message: "first".to_string(),
+            ..Default::default()
+        })
+        .with_warning(OctaveWarning {
+            message: "matrix singular to machine precision".to_string(),
+            identifier: Some("Octave:singular-matrix".to_string()),
+            ..Default::default()
+        })
+        .with_warning(OctaveWarning {
+            message: "after".to_string(),
+            ..Default::default()
+        })
+}
+
+#[test]
+fn promoted_warnings_become_errors() {
+    let fake = FakeBackend::new().on_script("x = inv(a)", singular());
+    let mut interp = Interpreter::with_backend(fake);
+    assert_eq!(interp.eval("x = inv(a)").warnings().len(), 3);
+
+    interp.promote_warning("Octave:singular-matrix");
+    let res = interp.eval("x = inv(a)");
+    let error = res.error().unwrap();
+    assert_eq!(error.message, "matrix singular to machine precision");
+    assert_eq!(error.identifier.as_deref(), Some("Octave:singular-matrix"));
+    assert_eq!(res.warnings().len(), 1);
+    assert!(matches!(
+        interp.try_eval("x = inv(a)"),
+        Err(MocktaveError::Octave { .. })
+    ));
+}
+
+#[test]
+fn calls_leave_nothing_behind() {
+    let fake = FakeBackend::new()
+        .on_call("max", |args| match &args[0] {
+            OctaveType::Matrix(rows) => {
+                let mut index = 0;
+                for (i, value) in rows[0].iter().enumerate() {
+                    if *value > rows[0][index] {
+                        index = i;
+                    }
+                }
+                vec![rows[0][index].into(), ((index + 1) as f64).into()]
+            }
+            _ => vec![],
+        })
+        .on_script("who", InterpreterResults::default());
+    let mut interp = Interpreter::with_backend(fake);
+    interp.set("kept", 1.0);
+
+    let value: f64 = interp.call("max", &[vec![3.0, 7.0, 5.0].into()]);
+    assert_eq!(value, 7.0);
+    let (value, index): (f64, f64) = interp.call_n("max", &[vec![3.0, 7.0, 5.0].into()]);
+    assert_eq!((value, index), (7.0, 2.0));
+
+    let names = interp
+        .eval("who")
+        .workspace()
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["kept"]);
+}
+
+#[test]
+fn failed_calls_leave_nothing_behind() {
+    let fake = FakeBackend::new()
+        .on_call("size", |_| vec![1.0.into()])
+        .on_script("who", InterpreterResults::default());
+    let mut interp = Interpreter::with_backend(fake);
+    interp.set("kept", 1.0);
+
+    let err = interp
+        .try_call_n::<(f64, f64)>("size", &[1.0.into()])
+        .unwrap_err();
+    assert!(matches!(err, MocktaveError::Parse { .. }));
+    let res = interp.eval("who");
+    assert_eq!(res.workspace().keys().collect::<Vec<_>>(), ["kept"]);
+}
+
+#[test]
+fn calls_without_arguments_keep_the_workspace() {
+    let scripts = Arc::new(Mutex::new(Vec::new()));
+    let fake = FakeBackend::new()
+        .on_call("pi", |_| vec![std::f64::consts::PI.into()])
+        .on_script("who", InterpreterResults::default());
+    let mut interp = Interpreter::with_backend(Recorder {
+        fake,
+        scripts: scripts.clone(),
+    });
+    interp.set("kept", 1.0);
+
+    let pi: f64 = interp.call("pi", &[]);
+    assert_eq!(pi, std::f64::consts::PI);
+    let res = interp.eval("who");
+    assert_eq!(res.get_scalar("kept"), Some(1.0));
+    assert_eq!(res.workspace().len(), 1);
+
+    // In Octave a `clear` without names clears everything
+    for script in scripts.lock().unwrap().iter() {
+        assert!(
+            script.lines().all(|line| line.trim() != "clear"),
+            "{script}"
+        );
+    }
+}
+
+#[test]
+fn unknown_functions_fail_loudly() {
+    let mut interp = Interpreter::with_backend(FakeBackend::new());
+    let err = interp.try_call::<f64>("norm", &[1.0.into()]).unwrap_err();
+    assert!(matches!(err, MocktaveError::Unmatched(script) if script.contains("norm(")));
+}
+
+#[test]
+fn closures_see_the_workspace() {
+    let fake = FakeBackend::new().on(|script, workspace| {
+        let a = workspace.get("a")?.clone();
+        (script == "b = 2 * a").then(|| match a {
+            OctaveType::Scalar(a) => InterpreterResults::default().with_variable("b", 2.0 * a),
+            _ => InterpreterResults::default(),
+        })
+    });
+    let mut interp = Interpreter::with_backend(fake);
+    assert!(interp.try_eval("b = 2 * a").is_err());
+    interp.set("a", 21.0);
+    let res = interp.eval("b = 2 * a");
+    assert_eq!(res.get_scalar("b"), Some(42.0));
+    assert_eq!(res.get_scalar("a"), Some(21.0));
+}
+
+#[test]
+fn async_interpreters_take_backends_too() {
+    let fake =
+        FakeBackend::new().on_script("disp(1)", InterpreterResults::default().with_stdout("1\n"));
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let mut interp = AsyncInterpreter::with_backend(fake);
+        interp.set_timeout(Some(Duration::from_secs(1)));
+        assert_eq!(interp.eval("disp(1)").await.stdout(), "1\n");
+        interp.close().await.unwrap();
+    });
+}
@@ -0,0 +1,68 @@
+//! Pins the Octave integer class that every Rust integer type is sent as, since changing it
+//! changes the results of arithmetic in Octave, and checks that converting back never wraps.
+
+use mocktave::{IntegerClass, NdArray, OctaveType};
+
+#[test]
+fn integers_keep_their_class() {
+    let cases: [(OctaveType, IntegerClass); 10] = [
+        (1_i8.into(), IntegerClass::Int8),
+        (1_i16.into(), IntegerClass::Int16),
+        (1_i32.into(), IntegerClass::Int32),
+        (1_i64.into(), IntegerClass::Int64),
+        (1_isize.into(), IntegerClass::Int64),
+        (1_u8.into(), IntegerClass::UInt8),
+        (1_u16.into(), IntegerClass::UInt16),
+        (1_u32.into(), IntegerClass::UInt32),
+        (1_u64.into(), IntegerClass::UInt64),
+        (1_usize.into(), IntegerClass::UInt64),
+    ];
+    for (value, class) in cases {
+        assert_eq!(value, OctaveType::Integer(class, 1));
+    }
+}
+
+#[test]
+fn collections_of_integers_keep_their_class() {
+    assert_eq!(
+        OctaveType::from(vec![1_i16, 2]),
+        OctaveType::IntegerMatrix(IntegerClass::Int16, vec![vec![1, 2]])
+    );
+    assert_eq!(
+        OctaveType::from(vec![vec![1_usize], vec![2]]),
+        OctaveType::IntegerMatrix(IntegerClass::UInt64, vec![vec![1], vec![2]])
+    );
+    assert_eq!(
+        OctaveType::from(NdArray::new(vec![1, 1, 2], vec![1_u8, 2]).unwrap()),
+        OctaveType::IntegerNdArray(
+            IntegerClass::UInt8,
+            NdArray::new(vec![1, 1, 2], vec![1, 2]).unwrap()
+        )
+    );
+}
+
+#[test]
+fn casting_to_f64_sends_doubles() {
+    let (n, m) = (7_i32, 2_usize);
+    assert_eq!(OctaveType::from(n as f64), OctaveType::Scalar(7.0));
+    assert_eq!(
+        OctaveType::from(vec![n as f64, m as f64]),
+        OctaveType::Matrix(vec![vec![7.0, 2.0]])
+    );
+}
+
+#[test]
+fn integers_that_do_not_fit_panic() {
+    let big = OctaveType::Integer(IntegerClass::Int64, 1 << 40);
+    assert_eq!(i64::from(big.clone()), 1 << 40);
+    assert!(std::panic::catch_unwind(|| i32::from(big.clone())).is_err());
+
+    let huge = OctaveType::Integer(IntegerClass::UInt64, 1 << 63);
+    assert_eq!(u64::from(huge.clone()), 1 << 63);
+    assert!(std::panic::catch_unwind(|| i64::from(huge.clone())).is_err());
+
+    let negative = OctaveType::Integer(IntegerClass::Int8, -1);
+    assert_eq!(i8::from(negative.clone()), -1);
+    assert!(std::panic::catch_unwind(|| u8::from(negative.clone())).is_err());
+    assert!(std::panic::catch_unwind(|| usize::from(negative.clone())).is_err());
+}
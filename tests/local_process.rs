@@ -0,0 +1,66 @@
+//! Drives the async interpreter through a stand-in for Octave, which checks how sessions talk to
+//! the process without needing Octave.
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use mocktave::Interpreter;
+
+/// Runs nothing, but prints the markers sessions wait for and echoes every script as its output,
+/// after a second for scripts that start with `pause`.
+const FAKE_OCTAVE: &str = r#"#!/bin/sh
+catching=no
+while IFS= read -r line; do
+    case "$line" in
+        catch*) catching=yes ;;
+        end_try_catch) catching=no ;;
+        '__mocktave_script__ = "'*)
+            script=${line#'__mocktave_script__ = "'}
+            script=${script%'";'} ;;
+        'eval(__mocktave_script__);')
+            case "$script" in pause*) sleep 1 ;; esac
+            printf '%s\n' "$script" ;;
+        'printf("'*'", "__mocktave_'*'");')
+            format=${line#'printf("'}
+            format=${format%%'", '*}
+            marker=${line#*'", "'}
+            marker=${marker%'");'}
+            [ $catching = no ] && printf "$format" "$marker" ;;
+        'fprintf(stderr, "'*'", "__mocktave_'*'");')
+            format=${line#'fprintf(stderr, "'}
+            format=${format%%'", '*}
+            marker=${line#*'", "'}
+            marker=${marker%'");'}
+            printf "$format" "$marker" >&2 ;;
+    esac
+done
+"#;
+
+fn fake_octave(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, FAKE_OCTAVE).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+fn dropped_evals_do_not_shift_later_results() {
+    let program = fake_octave("dropped-evals");
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let mut interp = Interpreter::builder()
+            .octave_path(program)
+            .try_build_async()
+            .await
+            .unwrap();
+        assert_eq!(interp.eval("disp(1)").await.stdout(), "disp(1)\n");
+
+        let dropped = tokio::time::timeout(Duration::from_millis(100), interp.eval("pause(1)"));
+        assert!(dropped.await.is_err());
+        assert_eq!(interp.eval("disp(2)").await.stdout(), "disp(2)\n");
+        assert_eq!(interp.eval("disp(3)").await.stdout(), "disp(3)\n");
+        interp.close().await.unwrap();
+    });
+}
@@ -0,0 +1,130 @@
+//! Shares fake interpreters between threads through a pool, checking that leases never see each
+//! other's state and that the pool never runs more interpreters than it was asked to.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use mocktave::{
+    FakeBackend, Interpreter, InterpreterPool, InterpreterResults, MocktaveError, OctaveError,
+    OctaveType, OctaveWarning,
+};
+
+/// Doubles `x` into `y`, failing on negative `x` and warning on `x = 0`.
+fn double(script: &str, workspace: &HashMap<String, OctaveType>) -> Option<InterpreterResults> {
+    if script != "y = 2 * x" {
+        return None;
+    }
+    Some(match workspace.get("x") {
+        Some(OctaveType::Scalar(x)) if *x < 0.0 => {
+            InterpreterResults::default().with_error(OctaveError {
+                message: format!("negative: {x}"),
+                identifier: Some("test:negative".to_string()),
+                ..Default::default()
+            })
+        }
+        Some(OctaveType::Scalar(x)) if *x == 0.0 => InterpreterResults::default()
+            .with_variable("y", 0.0)
+            .with_warning(OctaveWarning {
+                message: "zero".to_string(),
+                identifier: Some("test:zero".to_string()),
+                ..Default::default()
+            }),
+        Some(OctaveType::Scalar(x)) => InterpreterResults::default().with_variable("y", 2.0 * x),
+        _ => InterpreterResults::default(),
+    })
+}
+
+fn doubler() -> FakeBackend {
+    FakeBackend::new().on(double)
+}
+
+#[test]
+fn pools_can_be_shared_between_threads() {
+    fn shared<T: Send + Sync>() {}
+    shared::<InterpreterPool>();
+}
+
+#[test]
+fn leases_start_clean() {
+    let pool = Interpreter::builder().backend(doubler).build_pool(1);
+
+    let mut interp = pool.lease();
+    interp.set("x", 0.0);
+    interp.promote_warning("test:zero");
+    assert!(interp.eval("y = 2 * x").error().is_some());
+    drop(interp);
+
+    let mut interp = pool.lease();
+    assert!(interp.eval("y = 2 * x").workspace().is_empty());
+    interp.set("x", 0.0);
+    let res = interp.eval("y = 2 * x");
+    assert!(res.error().is_none());
+    assert_eq!(res.warnings().len(), 1);
+}
+
+#[test]
+fn sweeps_keep_their_order_and_stay_within_the_size() {
+    let started = Arc::new(AtomicUsize::new(0));
+    let running = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+    let pool = {
+        let (started, running, most) = (started.clone(), running.clone(), most.clone());
+        Interpreter::builder()
+            .backend(move || {
+                started.fetch_add(1, Ordering::SeqCst);
+                let (running, most) = (running.clone(), most.clone());
+                let slow = move |_: &str, _: &HashMap<String, OctaveType>| {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(10));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    None
+                };
+                FakeBackend::new().on(slow).on(double)
+            })
+            .build_pool(3)
+    };
+
+    let inputs = (1..=20).map(|x| [("x", x as f64)]);
+    let results = pool.map(inputs, "y = 2 * x");
+    let doubled = results
+        .iter()
+        .map(|res| res.get_scalar("y").unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        doubled,
+        (1..=20).map(|x| 2.0 * x as f64).collect::<Vec<_>>()
+    );
+    assert!(started.load(Ordering::SeqCst) <= 3);
+    assert!(most.load(Ordering::SeqCst) <= 3);
+}
+
+#[test]
+fn sweeps_report_the_first_failure() {
+    let pool = Interpreter::builder().backend(doubler).build_pool(2);
+    let inputs = [1.0, -2.0, 3.0, -4.0].map(|x| [("x", x)]);
+
+    let results = pool.map(inputs, "y = 2 * x");
+    assert_eq!(results[0].get_scalar("y"), Some(2.0));
+    assert_eq!(results[1].error().unwrap().message, "negative: -2");
+
+    let err = pool.try_map(inputs, "y = 2 * x").unwrap_err();
+    assert!(matches!(
+        err,
+        MocktaveError::Octave { message, identifier, .. }
+            if message == "negative: -2" && identifier.as_deref() == Some("test:negative")
+    ));
+}
+
+#[test]
+fn failed_starts_free_their_place() {
+    let pool = Interpreter::builder()
+        .octave_path("/does/not/exist/octave-cli")
+        .build_pool(1);
+    for _ in 0..2 {
+        let err = pool.try_lease().err().unwrap();
+        assert!(matches!(err, MocktaveError::BackendUnavailable(_)));
+    }
+}
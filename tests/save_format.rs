@@ -0,0 +1,455 @@
+//! Round trips every kind of value and randomly generated workspaces through the writer and the
+//! parser for Octave's text save format, and makes sure corrupted workspaces are reported as errors
+//! rather than panics.
+
+use std::collections::HashMap;
+
+use mocktave::{IntegerClass, InterpreterResults, MocktaveError, NdArray, OctaveType};
+
+/// A small xorshift generator, so every run sees the same workspaces.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn float(&mut self) -> f64 {
+        match self.below(10) {
+            0 => f64::NAN,
+            1 => f64::INFINITY,
+            2 => f64::NEG_INFINITY,
+            3 => f64::from_bits(self.next() & !(0x7ff << 52) | (0x3ff << 52)),
+            4 => (self.next() as i64) as f64 * 1e-300,
+            _ => (self.next() % 2001) as f64 / 8.0 - 125.0,
+        }
+    }
+
+    fn complex(&mut self) -> (f64, f64) {
+        (self.float(), self.float())
+    }
+
+    fn string(&mut self) -> String {
+        const PIECES: [&str; 10] = ["a", "Z", " ", "\"", "'", "\\", "\n", "# name: x", "é", "\t"];
+        (0..self.below(12))
+            .map(|_| PIECES[self.below(PIECES.len())])
+            .collect()
+    }
+
+    fn class(&mut self) -> IntegerClass {
+        [
+            IntegerClass::Int8,
+            IntegerClass::Int16,
+            IntegerClass::Int32,
+            IntegerClass::Int64,
+            IntegerClass::UInt8,
+            IntegerClass::UInt16,
+            IntegerClass::UInt32,
+            IntegerClass::UInt64,
+        ][self.below(8)]
+    }
+
+    fn integer(&mut self, class: IntegerClass) -> i128 {
+        let value = self.next() as i128;
+        match class {
+            IntegerClass::Int8 => value as i8 as i128,
+            IntegerClass::Int16 => value as i16 as i128,
+            IntegerClass::Int32 => value as i32 as i128,
+            IntegerClass::Int64 => value as i64 as i128,
+            IntegerClass::UInt8 => value as u8 as i128,
+            IntegerClass::UInt16 => value as u16 as i128,
+            IntegerClass::UInt32 => value as u32 as i128,
+            IntegerClass::UInt64 => value as u64 as i128,
+        }
+    }
+
+    fn matrix<T>(&mut self, mut element: impl FnMut(&mut Self) -> T) -> Vec<Vec<T>> {
+        let (rows, columns) = (1 + self.below(3), 1 + self.below(3));
+        (0..rows)
+            .map(|_| (0..columns).map(|_| element(self)).collect())
+            .collect()
+    }
+
+    fn nd_array<T>(&mut self, mut element: impl FnMut(&mut Self) -> T) -> NdArray<T> {
+        let mut shape = vec![1 + self.below(2), 1 + self.below(2), 2 + self.below(2)];
+        if self.below(2) == 0 {
+            shape.push(2);
+        }
+        let data = (0..shape.iter().product()).map(|_| element(self)).collect();
+        NdArray::new(shape, data).unwrap()
+    }
+
+    fn triplets<T>(
+        &mut self,
+        mut element: impl FnMut(&mut Self) -> T,
+    ) -> (usize, usize, Vec<(usize, usize, T)>) {
+        let (rows, cols) = (1 + self.below(4), 1 + self.below(4));
+        // Octave keeps the elements of a sparse matrix in column-major order
+        let mut triplets = Vec::new();
+        for j in 0..cols {
+            for i in 0..rows {
+                if self.below(3) == 0 {
+                    triplets.push((i, j, element(self)));
+                }
+            }
+        }
+        (rows, cols, triplets)
+    }
+
+    fn fields(&mut self, depth: usize) -> Vec<(String, OctaveType)> {
+        (0..1 + self.below(3))
+            .map(|idx| (format!("f{idx}"), self.value(depth + 1)))
+            .collect()
+    }
+
+    fn value(&mut self, depth: usize) -> OctaveType {
+        // Containers get rarer the deeper we are, so the workspaces stay small
+        let kinds = if depth > 2 { 16 } else { 19 };
+        match self.below(kinds) {
+            0 => OctaveType::Scalar(self.float()),
+            1 => {
+                let (re, im) = self.complex();
+                OctaveType::ComplexScalar(re, im)
+            }
+            2 => OctaveType::Matrix(self.matrix(Rng::float)),
+            3 => OctaveType::ComplexMatrix(self.matrix(Rng::complex)),
+            4 => {
+                let class = self.class();
+                OctaveType::Integer(class, self.integer(class))
+            }
+            5 => {
+                let class = self.class();
+                OctaveType::IntegerMatrix(class, self.matrix(|rng| rng.integer(class)))
+            }
+            6 => OctaveType::NdArray(self.nd_array(Rng::float)),
+            7 => OctaveType::ComplexNdArray(self.nd_array(Rng::complex)),
+            8 => OctaveType::BoolNdArray(self.nd_array(|rng| rng.below(2) == 0)),
+            9 => {
+                let class = self.class();
+                OctaveType::IntegerNdArray(class, self.nd_array(|rng| rng.integer(class)))
+            }
+            10 => {
+                let (rows, cols, triplets) = self.triplets(Rng::float);
+                OctaveType::SparseMatrix {
+                    rows,
+                    cols,
+                    triplets,
+                }
+            }
+            11 => {
+                let (rows, cols, triplets) = self.triplets(Rng::complex);
+                OctaveType::SparseComplexMatrix {
+                    rows,
+                    cols,
+                    triplets,
+                }
+            }
+            12 => {
+                let (rows, cols, triplets) = self.triplets(|_| true);
+                OctaveType::SparseBoolMatrix {
+                    rows,
+                    cols,
+                    triplets,
+                }
+            }
+            13 => OctaveType::String(self.string()),
+            14 => OctaveType::Bool(self.below(2) == 0),
+            15 => OctaveType::BoolMatrix(self.matrix(|rng| rng.below(2) == 0)),
+            16 => OctaveType::CellArray(self.matrix(|rng| rng.value(depth + 1))),
+            17 => OctaveType::Struct(self.fields(depth)),
+            _ => {
+                let (rows, columns) = (1 + self.below(2), 1 + self.below(3));
+                let names = (0..1 + self.below(3))
+                    .map(|idx| format!("f{idx}"))
+                    .collect::<Vec<_>>();
+                OctaveType::StructArray(
+                    (0..rows)
+                        .map(|_| {
+                            (0..columns)
+                                .map(|_| {
+                                    names
+                                        .iter()
+                                        .map(|name| (name.clone(), self.value(depth + 1)))
+                                        .collect()
+                                })
+                                .collect()
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    fn workspace(&mut self) -> HashMap<String, OctaveType> {
+        (0..1 + self.below(6))
+            .map(|idx| (format!("v{idx}"), self.value(0)))
+            .collect()
+    }
+}
+
+/// `NaN` isn't equal to itself, so compare the debug output instead.
+fn same(left: &OctaveType, right: &OctaveType) -> bool {
+    format!("{left:?}") == format!("{right:?}")
+}
+
+#[test]
+fn generated_workspaces_round_trip() {
+    let mut rng = Rng(0x5eed_1234_abcd_ef01);
+    for _ in 0..500 {
+        let workspace = rng.workspace();
+        let text = InterpreterResults::from(workspace.clone()).to_save_text();
+        let results = InterpreterResults::try_from(text.clone())
+            .unwrap_or_else(|error| panic!("{error}\n{text}"));
+        for (name, value) in &workspace {
+            let parsed = results
+                .get(name)
+                .unwrap_or_else(|| panic!("`{name}` is missing\n{text}"));
+            assert!(same(&parsed, value), "{parsed:?} != {value:?}\n{text}");
+        }
+    }
+}
+
+/// Write `value` as the only variable of a workspace and read it back.
+fn round_trip(value: &OctaveType) -> OctaveType {
+    let text = InterpreterResults::default()
+        .with_variable("x", value.clone())
+        .to_save_text();
+    InterpreterResults::try_from(text.clone())
+        .unwrap_or_else(|error| panic!("{error}\n{text}"))
+        .get("x")
+        .unwrap_or_else(|| panic!("`x` is missing\n{text}"))
+}
+
+#[test]
+fn every_variant_round_trips() {
+    let array = |shape: Vec<usize>| {
+        let count = shape.iter().product::<usize>();
+        NdArray::new(shape, (0..count).map(|el| el as f64).collect()).unwrap()
+    };
+    let fields = vec![
+        ("a".to_string(), OctaveType::Scalar(1.0)),
+        ("b".to_string(), OctaveType::String("two".to_string())),
+    ];
+    let mut values = vec![
+        OctaveType::Scalar(-0.5),
+        OctaveType::ComplexScalar(1.0, -2.0),
+        OctaveType::Matrix(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]),
+        OctaveType::ComplexMatrix(vec![vec![(1.0, 2.0)], vec![(3.0, -4.0)]]),
+        OctaveType::NdArray(array(vec![2, 1, 3])),
+        OctaveType::ComplexNdArray(array(vec![1, 2, 2]).map(|el| (el, -el))),
+        OctaveType::BoolNdArray(array(vec![2, 2, 2]).map(|el| el > 3.0)),
+        OctaveType::SparseMatrix {
+            rows: 3,
+            cols: 2,
+            triplets: vec![(0, 0, 1.5), (2, 1, -2.0)],
+        },
+        OctaveType::SparseComplexMatrix {
+            rows: 2,
+            cols: 2,
+            triplets: vec![(1, 0, (1.0, 2.0))],
+        },
+        OctaveType::SparseBoolMatrix {
+            rows: 2,
+            cols: 3,
+            triplets: vec![(0, 2, true)],
+        },
+        OctaveType::String("it's a \"string\"".to_string()),
+        OctaveType::Bool(true),
+        OctaveType::BoolMatrix(vec![vec![true, false], vec![false, true]]),
+        OctaveType::CellArray(vec![
+            vec![OctaveType::Scalar(1.0), OctaveType::String("a".to_string())],
+            vec![
+                OctaveType::Bool(false),
+                OctaveType::CellArray(vec![vec![OctaveType::Scalar(2.0)]]),
+            ],
+        ]),
+        OctaveType::Struct(fields.clone()),
+        OctaveType::StructArray(vec![vec![fields.clone(), fields]]),
+    ];
+    for (class, min, max) in [
+        (IntegerClass::Int8, i8::MIN as i128, i8::MAX as i128),
+        (IntegerClass::Int16, i16::MIN as i128, i16::MAX as i128),
+        (IntegerClass::Int32, i32::MIN as i128, i32::MAX as i128),
+        (IntegerClass::Int64, i64::MIN as i128, i64::MAX as i128),
+        (IntegerClass::UInt8, 0, u8::MAX as i128),
+        (IntegerClass::UInt16, 0, u16::MAX as i128),
+        (IntegerClass::UInt32, 0, u32::MAX as i128),
+        (IntegerClass::UInt64, 0, u64::MAX as i128),
+    ] {
+        values.push(OctaveType::Integer(class, min));
+        values.push(OctaveType::Integer(class, max));
+        values.push(OctaveType::IntegerMatrix(class, vec![vec![min, 0, max]]));
+        values.push(OctaveType::IntegerNdArray(
+            class,
+            NdArray::new(vec![1, 1, 2], vec![min, max]).unwrap(),
+        ));
+    }
+    for value in values {
+        let parsed = round_trip(&value);
+        assert!(same(&parsed, &value), "{parsed:?} != {value:?}");
+    }
+}
+
+#[test]
+fn empty_values_keep_their_shape() {
+    let no_rows = NdArray::new(vec![0, 3], Vec::new()).unwrap();
+    let values = vec![
+        OctaveType::Matrix(vec![vec![]; 2]),
+        OctaveType::NdArray(no_rows.clone()),
+        OctaveType::NdArray(NdArray::new(vec![2, 0, 3], Vec::new()).unwrap()),
+        OctaveType::ComplexNdArray(NdArray::new(vec![0, 2], Vec::new()).unwrap()),
+        OctaveType::BoolNdArray(NdArray::new(vec![0, 4], Vec::new()).unwrap()),
+        OctaveType::IntegerNdArray(IntegerClass::UInt8, no_rows.clone().map(|_| 0)),
+        OctaveType::IntegerMatrix(IntegerClass::Int16, vec![vec![]; 3]),
+        OctaveType::SparseMatrix {
+            rows: 0,
+            cols: 4,
+            triplets: Vec::new(),
+        },
+        OctaveType::SparseBoolMatrix {
+            rows: 3,
+            cols: 2,
+            triplets: Vec::new(),
+        },
+        OctaveType::String(String::new()),
+        OctaveType::CellArray(vec![vec![]; 2]),
+        OctaveType::StructArray(vec![vec![]; 2]),
+    ];
+    for value in values {
+        let parsed = round_trip(&value);
+        assert!(same(&parsed, &value), "{parsed:?} != {value:?}");
+    }
+
+    // Octave has one empty matrix, which is read as a matrix without rows or columns
+    assert_eq!(
+        round_trip(&OctaveType::Empty),
+        OctaveType::Matrix(Vec::new())
+    );
+    // Matrices without rows still convert like any other empty matrix
+    let parsed = round_trip(&OctaveType::NdArray(no_rows.clone()));
+    assert_eq!(parsed.try_into_vec_f64().unwrap(), Vec::<Vec<f64>>::new());
+    let parsed = round_trip(&OctaveType::NdArray(no_rows.clone()));
+    assert_eq!(Vec::<f64>::from(parsed), Vec::<f64>::new());
+    let parsed = round_trip(&OctaveType::IntegerNdArray(
+        IntegerClass::Int8,
+        no_rows.map(|_| 0),
+    ));
+    assert_eq!(parsed.try_into_vec_i128().unwrap(), Vec::<Vec<i128>>::new());
+}
+
+#[test]
+fn corrupted_workspaces_do_not_panic() {
+    let mut rng = Rng(0xdead_beef_0bad_f00d);
+    for _ in 0..500 {
+        let text = InterpreterResults::from(rng.workspace()).to_save_text();
+        let lines = text.split_inclusive('\n').collect::<Vec<_>>();
+        let line = rng.below(lines.len());
+        let corrupted = match rng.below(4) {
+            // Cut the output short
+            0 => text[..text.floor_char_boundary(rng.below(text.len()))].to_string(),
+            // Drop a line
+            1 => [&lines[..line], &lines[line + 1..]].concat().concat(),
+            // Repeat a line
+            2 => [&lines[..=line], &lines[line..]].concat().concat(),
+            // Change a number
+            _ => text.replacen(char::from(b'0' + rng.below(10) as u8), "9999999999", 1),
+        };
+        let _ = InterpreterResults::try_from(corrupted);
+    }
+}
+
+#[test]
+fn struct_array_fields_are_matched_by_name() {
+    let element = |a: f64, b: &str| {
+        vec![
+            ("a".to_string(), OctaveType::Scalar(a)),
+            ("b".to_string(), OctaveType::String(b.to_string())),
+        ]
+    };
+    let mut reordered = element(2.0, "y");
+    reordered.reverse();
+    let parsed = round_trip(&OctaveType::StructArray(vec![vec![
+        element(1.0, "x"),
+        reordered,
+    ]]));
+    assert_eq!(
+        parsed,
+        OctaveType::StructArray(vec![vec![element(1.0, "x"), element(2.0, "y")]])
+    );
+
+    // Elements with different fields can't be written, or sent to Octave
+    let mismatched = OctaveType::StructArray(vec![vec![
+        element(1.0, "x"),
+        vec![("a".to_string(), OctaveType::Scalar(2.0))],
+    ]]);
+    let workspace = InterpreterResults::default().with_variable("s", mismatched.clone());
+    assert!(matches!(
+        workspace.try_to_save_text(),
+        Err(MocktaveError::InvalidValue(_))
+    ));
+    let mut interp = mocktave::Interpreter::with_backend(mocktave::FakeBackend::new());
+    assert!(matches!(
+        interp.try_set("s", mismatched.clone()),
+        Err(MocktaveError::InvalidValue(_))
+    ));
+    assert!(mismatched.to_octave_literal().starts_with("error("));
+}
+
+#[test]
+fn struct_array_fields_of_the_wrong_size_are_errors() {
+    let element = vec![("a".to_string(), OctaveType::Scalar(1.0))];
+    let text = InterpreterResults::default()
+        .with_variable("s", OctaveType::StructArray(vec![vec![element; 2]]))
+        .to_save_text();
+    assert!(text.contains("# ndims: 2\n 1 2\n"));
+    let text = text.replace("# ndims: 2\n 1 2\n", "# ndims: 2\n 1 3\n");
+    assert!(matches!(
+        InterpreterResults::try_from(text),
+        Err(MocktaveError::Parse { .. })
+    ));
+}
+
+#[test]
+fn errors_point_at_the_problem() {
+    let text =
+        "# Created by Octave\n# name: a\n# type: matrix\n# rows: 2\n# columns: 2\n 1 2\n 3 x\n";
+    match InterpreterResults::try_from(text.to_string()) {
+        Err(MocktaveError::Parse {
+            text, line, column, ..
+        }) => {
+            assert_eq!(text, "x");
+            assert_eq!(line, Some(7));
+            assert_eq!(column, Some(4));
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn printed_blocks_inside_strings_are_not_variables() {
+    let workspace = HashMap::from([(
+        "a".to_string(),
+        OctaveType::CellArray(vec![vec![OctaveType::Struct(vec![(
+            "s".to_string(),
+            OctaveType::String("# name: b\n# type: scalar\n1".to_string()),
+        )])]]),
+    )]);
+    let results =
+        InterpreterResults::try_from(InterpreterResults::from(workspace).to_save_text()).unwrap();
+    assert_eq!(results.get("b"), None);
+    assert_eq!(
+        results
+            .get_path("a(1).s")
+            .unwrap()
+            .try_into_string()
+            .unwrap(),
+        "# name: b\n# type: scalar\n1"
+    );
+}
@@ -0,0 +1,56 @@
+//! Checks that the containers behind the free functions are cleaned up when the process exits,
+//! with a stand-in for the `docker` command line client that logs how it was called.
+#![cfg(all(unix, feature = "docker"))]
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Set in the child process that uses the free functions and then exits.
+const CHILD: &str = "MOCKTAVE_SHARED_TEST_CHILD";
+
+/// Logs its arguments, and lists a single container when asked for containers.
+const FAKE_DOCKER: &str = r#"#!/bin/sh
+echo "$*" >> "$MOCKTAVE_DOCKER_LOG"
+if [ "$1" = ps ]; then
+    echo 0123abcd
+fi
+"#;
+
+#[test]
+fn shared_containers_are_removed_at_exit() {
+    if env::var_os(CHILD).is_some() {
+        // There is no Docker daemon to start a container, but the pool is there all the same
+        assert!(mocktave::try_eval("x = 1").is_err());
+        return;
+    }
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("fake-docker");
+    fs::create_dir_all(&dir).unwrap();
+    let docker = dir.join("docker");
+    fs::write(&docker, FAKE_DOCKER).unwrap();
+    fs::set_permissions(&docker, fs::Permissions::from_mode(0o755)).unwrap();
+    let log = dir.join("log");
+    let _ = fs::remove_file(&log);
+
+    let path = format!("{}:{}", dir.display(), env::var("PATH").unwrap_or_default());
+    let mut child = Command::new(env::current_exe().unwrap())
+        .args(["--exact", "shared_containers_are_removed_at_exit"])
+        .env(CHILD, "1")
+        .env("PATH", path)
+        .env("DOCKER_HOST", "unix:///does/not/exist/docker.sock")
+        .env("MOCKTAVE_DOCKER_LOG", &log)
+        .env_remove("MOCKTAVE_ISOLATE")
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let pid = child.id();
+    assert!(child.wait().unwrap().success());
+
+    assert_eq!(
+        fs::read_to_string(&log).unwrap(),
+        format!("ps --all --quiet --filter label=mocktave.shared={pid}\nrm --force 0123abcd\n")
+    );
+}